@@ -0,0 +1,63 @@
+//! Timings of the parser on a large cookbook-like input.
+//!
+//! Run with `cargo bench -p recipe-parser`. Criterion keeps the results of
+//! the previous run and reports the change against them.
+
+use std::hint::black_box;
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use recipe_parser::{Recipe, RenderOptions, parse, render_text};
+
+const RECIPE: &str = r#">> name: Potato soup
+>> tags: vegan, easy
+>> servings: 4
+
+= Soup
+
+Peel the {potatoes}(1 kg) and cut them in cubes with a &{knife}.
+Boil them in a &{pot} with {water}(2 l) and {salt}(1 pinch) for t{25 minutes}.
+/* stir from time to time */
+Add the {leeks}(2) and @{woile/vegetable stock}(500 ml), then blend until smooth.
+Serve with [bread](https://example.com/bread) and a drizzle of {olive oil}(2 tbsp).
+"#;
+
+/// A file with as many recipes as needed to reach the number of lines
+fn cookbook(lines: usize) -> String {
+    let per_recipe = RECIPE.lines().count();
+    RECIPE.repeat(lines / per_recipe + 1)
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let large = cookbook(10_000);
+    let words = "Let it rest and serve warm, ".repeat(10_000);
+
+    let mut group = c.benchmark_group("parse");
+    for (name, input) in [
+        ("recipe", RECIPE),
+        ("10k lines", large.as_str()),
+        ("plain words", words.as_str()),
+    ] {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_function(name, |b| b.iter(|| parse(black_box(input)).unwrap()));
+    }
+    group.finish();
+}
+
+fn bench_recipe(c: &mut Criterion) {
+    let large = cookbook(10_000);
+    c.bench_function("recipe/10k lines", |b| {
+        b.iter(|| Recipe::try_from(black_box(large.as_str())).unwrap())
+    });
+}
+
+fn bench_render(c: &mut Criterion) {
+    let large = cookbook(10_000);
+    let tokens = parse(&large).unwrap();
+    let options = RenderOptions::default();
+    c.bench_function("render_text/10k lines", |b| {
+        b.iter(|| render_text(black_box(&tokens), &options))
+    });
+}
+
+criterion_group!(benches, bench_parse, bench_recipe, bench_render);
+criterion_main!(benches);
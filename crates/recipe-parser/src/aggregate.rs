@@ -0,0 +1,658 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{Quantity, Recipe, Token, Unit, UnitRegistry};
+
+/// Maps ingredient synonyms and plural forms to a canonical name,
+/// so `Tomatoes` and `tomato` are aggregated together.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngredientNormalizer {
+    synonyms: HashMap<String, String>,
+}
+
+impl IngredientNormalizer {
+    /// Normalizer using only the built-in English plural rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Normalizer with user provided synonyms, like `scallion` to `spring onion`.
+    ///
+    /// Both sides are matched case-insensitively.
+    pub fn with_synonyms(synonyms: HashMap<String, String>) -> Self {
+        let synonyms = synonyms
+            .into_iter()
+            .map(|(k, v)| (normalize_name(&k), normalize_name(&v)))
+            .collect();
+        Self { synonyms }
+    }
+
+    /// Canonical name of an ingredient
+    ///
+    /// ```
+    /// use recipe_parser::IngredientNormalizer;
+    ///
+    /// let normalizer = IngredientNormalizer::new();
+    /// assert_eq!(normalizer.normalize("Cherry Tomatoes"), "cherry tomato");
+    /// ```
+    pub fn normalize(&self, name: &str) -> String {
+        let name = normalize_name(name);
+        if let Some(canonical) = self.synonyms.get(&name) {
+            return canonical.clone();
+        }
+        let singular = singularize(&name);
+        match self.synonyms.get(&singular) {
+            Some(canonical) => canonical.clone(),
+            None => singular,
+        }
+    }
+}
+
+/// Name the ingredients are matched by, with the normalizer or else exactly
+fn ingredient_key(name: &str, normalizer: Option<&IngredientNormalizer>) -> String {
+    match normalizer {
+        Some(normalizer) => normalizer.normalize(name),
+        None => normalize_name(name),
+    }
+}
+
+/// Exact matching of names: trimmed and case-insensitive
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Strip the English plural of the last word, e.g: `tomatoes` -> `tomato`
+fn singularize(name: &str) -> String {
+    let (head, word) = match name.rsplit_once(' ') {
+        Some((head, word)) => (Some(head), word),
+        None => (None, name),
+    };
+    let singular = if word.len() > 4 && word.ends_with("ies") {
+        format!("{}y", &word[..word.len() - 3])
+    } else if word.len() > 3
+        && ["oes", "ches", "shes", "xes", "sses"]
+            .iter()
+            .any(|suffix| word.ends_with(suffix))
+    {
+        word[..word.len() - 2].to_string()
+    } else if word.len() > 3
+        && word.ends_with('s')
+        && !["ss", "us", "is"]
+            .iter()
+            .any(|suffix| word.ends_with(suffix))
+    {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    };
+    match head {
+        Some(head) => format!("{head} {singular}"),
+        None => singular,
+    }
+}
+
+/// Sum of the amounts of an ingredient in one unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Total {
+    /// `None` for the amounts that don't have a numeric quantity, like
+    /// `{salt}(a few tsp)`, they're kept apart from the numeric ones
+    pub quantity: Option<Quantity>,
+    pub unit: Option<String>,
+}
+
+/// An ingredient with all its amounts summed up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatedIngredient {
+    /// The name as it was first written
+    pub name: String,
+    /// One total per unit, in the order they first appear. A unit has a
+    /// second total for the amounts without a numeric quantity, and another
+    /// one each time a sum is too large for a [`Quantity`], so no amount is
+    /// left out.
+    pub totals: Vec<Total>,
+}
+
+/// Sum the amounts of the ingredients that appear more than once.
+///
+/// Only the primary amount of each ingredient is summed, and only amounts
+/// in the same unit are added together. Without a normalizer, names match
+/// when they are equal ignoring case and surrounding spaces.
+///
+/// Units match when the [`UnitRegistry`] resolves them to the same one, like
+/// `gr` and `grams`. Counts of something that isn't a measurement, like
+/// `(2 cloves)` and `(1 clove)`, are summed by the singular of the word.
+///
+/// ```
+/// use recipe_parser::{aggregate, parse, Quantity};
+///
+/// let tokens = parse("{Tomato}(2) and later {tomato}(1)").unwrap();
+/// let ingredients = aggregate(&tokens, None);
+/// assert_eq!(ingredients.len(), 1);
+/// assert_eq!(ingredients[0].totals[0].quantity, Quantity::new(3, 1));
+/// ```
+pub fn aggregate(
+    tokens: &[Token],
+    normalizer: Option<&IngredientNormalizer>,
+) -> Vec<AggregatedIngredient> {
+    let mut aggregated: Vec<AggregatedIngredient> = vec![];
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let registry = UnitRegistry::new();
+
+    for token in tokens {
+        let Token::Ingredient { name, amounts } = token else {
+            continue;
+        };
+        let key = ingredient_key(name, normalizer);
+        let position = *positions.entry(key).or_insert_with(|| {
+            aggregated.push(AggregatedIngredient {
+                name: name.trim().to_string(),
+                totals: vec![],
+            });
+            aggregated.len() - 1
+        });
+        let Some(amount) = amounts.first() else {
+            continue;
+        };
+        let unit = amount.unit.as_deref().map(|u| u.to_string());
+        let quantity = amount.quantity_value();
+        let key = amount.unit.as_deref().map(|unit| unit_key(&registry, unit));
+        let totals = &mut aggregated[position].totals;
+        let same_unit = totals.iter().rposition(|t| {
+            t.quantity.is_some() == quantity.is_some()
+                && t.unit.as_deref().map(|unit| unit_key(&registry, unit)) == key
+        });
+        match (same_unit, quantity) {
+            (Some(index), Some(quantity)) => match totals[index]
+                .quantity
+                .and_then(|total| total.checked_add(quantity))
+            {
+                Some(sum) => totals[index].quantity = Some(sum),
+                None => totals.push(Total {
+                    quantity: Some(quantity),
+                    unit,
+                }),
+            },
+            // Amounts without a numeric quantity have nothing to add
+            (Some(_), None) => {}
+            (None, quantity) => totals.push(Total { quantity, unit }),
+        }
+    }
+    aggregated
+}
+
+/// Order of the ingredients in a list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IngredientSort {
+    /// In the order they first appear in the recipe
+    #[default]
+    Source,
+    /// By name, ignoring case
+    Alphabetical,
+    /// The largest amount first, by the first total in grams or milliliters,
+    /// taking one milliliter as one gram. Ingredients whose first total has
+    /// no quantity or no unit of a known size, like `(2)` or `(3 cloves)`,
+    /// go last.
+    QuantityDescending,
+}
+
+/// Sort aggregated ingredients, ties keep the order they had.
+///
+/// ```
+/// use recipe_parser::{aggregate, parse, sort_ingredients, IngredientSort};
+///
+/// let tokens = parse("{salt}(1 pinch) {flour}(500 gr) {water}(300 gr)").unwrap();
+/// let mut ingredients = aggregate(&tokens, None);
+/// sort_ingredients(&mut ingredients, IngredientSort::QuantityDescending);
+/// let names: Vec<_> = ingredients.iter().map(|i| i.name.as_str()).collect();
+/// assert_eq!(names, vec!["flour", "water", "salt"]);
+/// ```
+pub fn sort_ingredients(ingredients: &mut [AggregatedIngredient], sort: IngredientSort) {
+    match sort {
+        IngredientSort::Source => {}
+        IngredientSort::Alphabetical => {
+            ingredients.sort_by_cached_key(|ingredient| normalize_name(&ingredient.name))
+        }
+        IngredientSort::QuantityDescending => {
+            let registry = UnitRegistry::new();
+            ingredients.sort_by_cached_key(|ingredient| {
+                let quantity = ingredient.totals.first().and_then(|total| {
+                    let factor = registry.resolve(total.unit.as_deref()?).base_factor()?;
+                    total.quantity?.checked_mul(factor)
+                });
+                // `None` is the smallest, and reversed it goes last
+                core::cmp::Reverse(quantity)
+            })
+        }
+    }
+}
+
+/// Units are compared by their canonical symbol, or the singular of the word
+/// for counts like `cloves`
+fn unit_key(registry: &UnitRegistry, unit: &str) -> String {
+    match registry.resolve(unit) {
+        Unit::Custom(unit) => singularize(&normalize_name(&unit)),
+        unit => unit.symbol().to_string(),
+    }
+}
+
+/// Count in how many recipes each ingredient appears, the most common first.
+///
+/// Names are matched like in [`aggregate`] with the same normalizer, and
+/// are returned as they're matched: trimmed and in lowercase, or the
+/// canonical name of the normalizer. Ingredients with the same count are
+/// sorted by name.
+///
+/// ```
+/// use recipe_parser::{ingredient_frequencies, IngredientNormalizer, Recipe};
+///
+/// let soup = Recipe::try_from("{Salt} and {tomatoes} with {water}").unwrap();
+/// let salad = Recipe::try_from("{salt} and {tomato}").unwrap();
+/// let recipes = [soup, salad];
+/// let frequencies = ingredient_frequencies(&recipes, None);
+/// assert_eq!(frequencies[0], ("salt".to_string(), 2));
+///
+/// let normalizer = IngredientNormalizer::new();
+/// let frequencies = ingredient_frequencies(&recipes, Some(&normalizer));
+/// assert_eq!(frequencies[1], ("tomato".to_string(), 2));
+/// ```
+pub fn ingredient_frequencies(
+    recipes: &[Recipe],
+    normalizer: Option<&IngredientNormalizer>,
+) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for recipe in recipes {
+        let names: HashSet<String> = recipe
+            .ingredients
+            .iter()
+            .map(|ingredient| ingredient_key(&ingredient.name, normalizer))
+            .collect();
+        for name in names {
+            *counts.entry(name).or_default() += 1;
+        }
+    }
+    let mut frequencies: Vec<(String, usize)> = counts.into_iter().collect();
+    frequencies.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+    frequencies
+}
+
+/// Shopping list of a recipe and the recipes it references with `@{}`,
+/// looked up by name in `recipes`. The ingredients of a referenced recipe
+/// are added once per reference, and the references it has are followed
+/// too, except the ones back to a recipe being resolved.
+///
+/// References that are not in `recipes` are left out, compare
+/// [`Recipe::recipe_refs`] with the keys to find them. The amount of a
+/// reference, like `@{sauce}(200 ml)`, doesn't scale the referenced recipe.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use recipe_parser::{resolve_refs, Recipe};
+///
+/// let dough = Recipe::try_from("Mix {flour}(500 gr) and {water}(300 ml)").unwrap();
+/// let pizza = Recipe::try_from("Top the @recipe{dough} with {flour}(10 gr) and {cheese}").unwrap();
+/// let recipes = HashMap::from([("dough".to_string(), dough)]);
+/// let names: Vec<String> = resolve_refs(&pizza, &recipes)
+///     .into_iter()
+///     .map(|ingredient| ingredient.name)
+///     .collect();
+/// assert_eq!(names, ["flour", "water", "cheese"]);
+/// ```
+pub fn resolve_refs(
+    recipe: &Recipe,
+    recipes: &HashMap<String, Recipe>,
+) -> Vec<AggregatedIngredient> {
+    let mut tokens = Vec::new();
+    collect_ingredients(recipe, recipes, &mut vec![], &mut tokens);
+    aggregate(&tokens, None)
+}
+
+fn collect_ingredients<'a>(
+    recipe: &'a Recipe,
+    recipes: &'a HashMap<String, Recipe>,
+    resolving: &mut Vec<&'a str>,
+    tokens: &mut Vec<Token<'a>>,
+) {
+    for token in &recipe.instructions {
+        match token {
+            Token::Ingredient { .. } => tokens.push(token.clone()),
+            Token::RecipeRef { name, .. } if !resolving.contains(name) => {
+                if let Some(referenced) = recipes.get(*name) {
+                    resolving.push(name);
+                    collect_ingredients(referenced, recipes, resolving, tokens);
+                    resolving.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Amount, parse};
+    use rstest::*;
+
+    #[rstest]
+    #[case("tomatoes", "tomato")]
+    #[case("Tomatoes", "tomato")]
+    #[case(" tomato ", "tomato")]
+    #[case("berries", "berry")]
+    #[case("eggs", "egg")]
+    #[case("peaches", "peach")]
+    #[case("cherry tomatoes", "cherry tomato")]
+    #[case("hummus", "hummus")]
+    #[case("couscous", "couscous")]
+    #[case("peas", "pea")]
+    #[case("gas", "gas")]
+    fn test_normalize_plurals(#[case] name: &str, #[case] expected: &str) {
+        let normalizer = IngredientNormalizer::new();
+        assert_eq!(normalizer.normalize(name), expected);
+    }
+
+    #[test]
+    fn test_normalize_synonyms() {
+        let synonyms = HashMap::from([
+            ("Scallion".to_string(), "spring onion".to_string()),
+            ("courgette".to_string(), "zucchini".to_string()),
+        ]);
+        let normalizer = IngredientNormalizer::with_synonyms(synonyms);
+        assert_eq!(normalizer.normalize("scallions"), "spring onion");
+        assert_eq!(normalizer.normalize("Courgette"), "zucchini");
+        assert_eq!(normalizer.normalize("leeks"), "leek");
+    }
+
+    #[test]
+    fn test_aggregate_without_normalizer_is_exact() {
+        let tokens = parse("{Tomato}(2) {tomato }(1) {tomatoes}(3)").unwrap();
+        let ingredients = aggregate(&tokens, None);
+        assert_eq!(
+            ingredients,
+            vec![
+                AggregatedIngredient {
+                    name: "Tomato".to_string(),
+                    totals: vec![Total {
+                        quantity: Quantity::new(3, 1),
+                        unit: None
+                    }]
+                },
+                AggregatedIngredient {
+                    name: "tomatoes".to_string(),
+                    totals: vec![Total {
+                        quantity: Quantity::new(3, 1),
+                        unit: None
+                    }]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ingredient_frequencies() {
+        let recipes: Vec<Recipe> = [
+            "{Salt} and {salt} with {water}",
+            "{water}, {pepper} and {salt }",
+            "{pepper} and {oil}",
+            "Boil",
+        ]
+        .into_iter()
+        .map(|input| Recipe::try_from(input).unwrap())
+        .collect();
+        assert_eq!(
+            ingredient_frequencies(&recipes, None),
+            vec![
+                ("pepper".to_string(), 2),
+                ("salt".to_string(), 2),
+                ("water".to_string(), 2),
+                ("oil".to_string(), 1),
+            ]
+        );
+        assert!(ingredient_frequencies(&[], None).is_empty());
+    }
+
+    #[test]
+    fn test_ingredient_frequencies_with_normalizer() {
+        let recipes: Vec<Recipe> = [
+            "{tomatoes} and {scallion}",
+            "{Tomato} with {green onions}",
+            "{scallions}",
+        ]
+        .into_iter()
+        .map(|input| Recipe::try_from(input).unwrap())
+        .collect();
+        let normalizer = IngredientNormalizer::with_synonyms(HashMap::from([(
+            "scallion".to_string(),
+            "green onion".to_string(),
+        )]));
+        assert_eq!(
+            ingredient_frequencies(&recipes, Some(&normalizer)),
+            vec![("green onion".to_string(), 3), ("tomato".to_string(), 2)]
+        );
+        assert_eq!(ingredient_frequencies(&recipes, None).len(), 5);
+    }
+
+    #[test]
+    fn test_aggregate_with_normalizer() {
+        let tokens = parse("{Tomato}(2) {tomatoes}(3) {salt} {salts}").unwrap();
+        let normalizer = IngredientNormalizer::new();
+        let ingredients = aggregate(&tokens, Some(&normalizer));
+        let names: Vec<_> = ingredients.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["Tomato", "salt"]);
+        assert_eq!(ingredients[0].totals[0].quantity, Quantity::new(5, 1));
+        assert!(ingredients[1].totals.is_empty());
+    }
+
+    #[rstest]
+    #[case(IngredientSort::Source, vec!["Salt", "flour", "water", "pepper", "eggs"])]
+    #[case(IngredientSort::Alphabetical, vec!["eggs", "flour", "pepper", "Salt", "water"])]
+    #[case(IngredientSort::QuantityDescending, vec!["flour", "water", "Salt", "pepper", "eggs"])]
+    fn test_sort_ingredients(#[case] sort: IngredientSort, #[case] expected: Vec<&str>) {
+        let tokens = parse(
+            "{Salt}(1/2 tsp) {flour}(500 gr) {water}(300 ml) {salt}(1/2 tsp) {pepper} {eggs}(2)",
+        )
+        .unwrap();
+        let mut ingredients = aggregate(&tokens, None);
+        sort_ingredients(&mut ingredients, sort);
+        let names: Vec<&str> = ingredients.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, expected);
+    }
+
+    #[rstest]
+    #[case("{b}(500 gr) {a}(1 kg)", vec!["a", "b"])]
+    #[case("{a}(1 cup) {b}(300 ml)", vec!["b", "a"])]
+    #[case("{a}(1 lb) {b}(1 l) {c}(2 tbsp) {d}(100 mg)", vec!["b", "a", "c", "d"])]
+    #[case("{a}(3) {b}(2 cloves) {c}(1 pinch) {d}(1 gr)", vec!["d", "a", "b", "c"])]
+    fn test_sort_ingredients_by_quantity_converts_units(
+        #[case] input: &str,
+        #[case] expected: Vec<&str>,
+    ) {
+        let mut ingredients = aggregate(&parse(input).unwrap(), None);
+        sort_ingredients(&mut ingredients, IngredientSort::QuantityDescending);
+        let names: Vec<&str> = ingredients.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_aggregate_counts() {
+        let tokens = parse(
+            "{garlic}(2 cloves) {garlic}(1 clove) {garlic}(3 Cloves) {parsley}(1 bunch) {parsley}(2 bunches) {eggs}(3 large) {eggs}(1 large) {eggs}(2)",
+        )
+        .unwrap();
+        let ingredients = aggregate(&tokens, None);
+        let totals: Vec<Vec<(Option<Quantity>, Option<&str>)>> = ingredients
+            .iter()
+            .map(|i| {
+                i.totals
+                    .iter()
+                    .map(|t| (t.quantity, t.unit.as_deref()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                vec![(Quantity::new(6, 1), Some("cloves"))],
+                vec![(Quantity::new(3, 1), Some("bunch"))],
+                vec![
+                    (Quantity::new(4, 1), Some("large")),
+                    (Quantity::new(2, 1), None)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_units_with_different_spellings() {
+        let tokens =
+            parse("{flour}(100 gr) {flour}(50 grams) {flour}(1 kg) {flour}(1 cup)").unwrap();
+        let ingredients = aggregate(&tokens, None);
+        assert_eq!(
+            ingredients[0].totals,
+            vec![
+                Total {
+                    quantity: Quantity::new(150, 1),
+                    unit: Some("gr".to_string())
+                },
+                Total {
+                    quantity: Quantity::new(1, 1),
+                    unit: Some("kg".to_string())
+                },
+                Total {
+                    quantity: Quantity::new(1, 1),
+                    unit: Some("cup".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_keeps_units_apart_and_uses_primary_amount() {
+        let tokens =
+            parse("{butter}(113 gr / 1 stick) {butter}(50 GR) {butter}(1 tbsp) {butter}(1/2 tbsp)")
+                .unwrap();
+        let ingredients = aggregate(&tokens, None);
+        assert_eq!(
+            ingredients[0].totals,
+            vec![
+                Total {
+                    quantity: Quantity::new(163, 1),
+                    unit: Some("gr".to_string())
+                },
+                Total {
+                    quantity: Quantity::new(3, 2),
+                    unit: Some("tbsp".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_keeps_amounts_without_quantity_apart() {
+        let tokens = vec![
+            Token::Ingredient {
+                name: "salt".into(),
+                amounts: vec![Amount::new(Some("1"), Some("tsp"))],
+            },
+            Token::Ingredient {
+                name: "salt".into(),
+                amounts: vec![Amount::new(Some("a few"), Some("tsp"))],
+            },
+            Token::Ingredient {
+                name: "salt".into(),
+                amounts: vec![Amount::new(Some("2"), Some("tsp"))],
+            },
+            Token::Ingredient {
+                name: "salt".into(),
+                amounts: vec![Amount::new(Some("1/0"), Some("tsp"))],
+            },
+        ];
+        let ingredients = aggregate(&tokens, None);
+        assert_eq!(
+            ingredients[0].totals,
+            vec![
+                Total {
+                    quantity: Quantity::new(3, 1),
+                    unit: Some("tsp".to_string())
+                },
+                Total {
+                    quantity: None,
+                    unit: Some("tsp".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_keeps_going_after_an_overflow() {
+        let tokens =
+            parse("{salt}(9223372036854775807 g) {salt}(1 g) {salt}(2 g) {salt}(3 g)").unwrap();
+        let ingredients = aggregate(&tokens, None);
+        assert_eq!(
+            ingredients[0].totals,
+            vec![
+                Total {
+                    quantity: Quantity::new(i64::MAX, 1),
+                    unit: Some("g".to_string())
+                },
+                Total {
+                    quantity: Quantity::new(6, 1),
+                    unit: Some("g".to_string())
+                },
+            ]
+        );
+    }
+
+    fn recipes<'a>(sources: &[(&str, &'a str)]) -> HashMap<String, Recipe<'a>> {
+        sources
+            .iter()
+            .map(|(name, source)| (name.to_string(), Recipe::try_from(*source).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_refs_nested() {
+        let recipes = recipes(&[
+            ("dough", "Mix {flour}(500 gr) with {salt}(1 tsp)"),
+            (
+                "sauce",
+                "Cook {tomatoes}(4) with {salt}(1 tsp) and @{herbs}",
+            ),
+            ("herbs", "Chop {basil}"),
+        ]);
+        let pizza =
+            Recipe::try_from("Top @recipe{dough} with @{sauce}(1 cup) and {salt}(1 tsp)").unwrap();
+        let ingredients: Vec<(String, Vec<Total>)> = resolve_refs(&pizza, &recipes)
+            .into_iter()
+            .map(|ingredient| (ingredient.name, ingredient.totals))
+            .collect();
+        let total = |quantity: i64, unit: Option<&str>| Total {
+            quantity: Quantity::new(quantity, 1),
+            unit: unit.map(str::to_string),
+        };
+        assert_eq!(
+            ingredients,
+            vec![
+                ("flour".to_string(), vec![total(500, Some("gr"))]),
+                ("salt".to_string(), vec![total(3, Some("tsp"))]),
+                ("tomatoes".to_string(), vec![total(4, None)]),
+                ("basil".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("Use @{missing} and {salt}", vec!["salt"])]
+    #[case("Use @{loop} and {salt}", vec!["pepper", "salt"])]
+    #[case("Use @{loop} twice @{loop}", vec!["pepper"])]
+    fn test_resolve_refs_missing_and_cycles(#[case] source: &str, #[case] expected: Vec<&str>) {
+        let recipes = recipes(&[
+            ("loop", "Add {pepper} and @{loop} and @{again}"),
+            ("again", "@{loop}"),
+        ]);
+        let recipe = Recipe::try_from(source).unwrap();
+        let names: Vec<String> = resolve_refs(&recipe, &recipes)
+            .into_iter()
+            .map(|ingredient| ingredient.name)
+            .collect();
+        assert_eq!(names, expected);
+    }
+}
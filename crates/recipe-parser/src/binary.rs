@@ -0,0 +1,269 @@
+//! Compact binary encoding of the tokens.
+//!
+//! The tokens are encoded with [postcard](https://docs.rs/postcard), after a
+//! header of the format version. postcard is not self-describing, so the
+//! version must be bumped whenever the tokens change shape, and the bytes of
+//! any other version are rejected instead of misread.
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Amount, TimerKind, Token};
+
+/// Start of the encoded tokens, the last byte is the version of the format
+const MAGIC: &[u8] = b"rcp\x01";
+
+/// Error of bytes that are not tokens encoded with [`to_bytes`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Not encoded tokens, or encoded by another version of the format
+    Version,
+    /// The tokens after the header are not valid
+    Invalid(postcard::Error),
+    /// Bytes left after the tokens, at the given offset
+    TrailingBytes(usize),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::Version => write!(f, "not encoded tokens, or of another version"),
+            DecodeError::Invalid(error) => write!(f, "invalid tokens: {}", error),
+            DecodeError::TrailingBytes(offset) => {
+                write!(f, "unexpected bytes after the tokens at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// Encode the tokens in a compact binary format, e.g: to cache parsed
+/// recipes. Read them back with [`from_bytes`].
+///
+/// The format is versioned, the bytes of another version are rejected
+/// instead of misread.
+///
+/// A `Recipe` is not encoded as such: encode its `instructions`, which are
+/// all the tokens it was grouped from, and group the decoded tokens again
+/// with `Recipe::from_tokens`. The spans of the ingredients in the source
+/// are not in the tokens, so they're lost.
+///
+/// ```
+/// use recipe_parser::{from_bytes, parse, to_bytes, Recipe};
+///
+/// let tokens = parse("Boil the {water}(1 l) for t{5 min}").unwrap();
+/// let bytes = to_bytes(&tokens);
+/// assert_eq!(from_bytes(&bytes).unwrap(), tokens);
+///
+/// let recipe = Recipe::try_from(">> name: tea\nSteep the {tea}(2 tsp)").unwrap();
+/// let bytes = to_bytes(&recipe.instructions);
+/// let cached = Recipe::from_tokens(from_bytes(&bytes).unwrap());
+/// assert_eq!(cached.instructions, recipe.instructions);
+/// assert_eq!(cached.ingredients[0].span, None);
+/// ```
+pub fn to_bytes(tokens: &[Token]) -> Vec<u8> {
+    let tokens: Vec<Encoded> = tokens.iter().map(Encoded::from).collect();
+    postcard::to_extend(&tokens, Vec::from(MAGIC)).expect("writing to a Vec cannot fail")
+}
+
+/// Decode the tokens written by [`to_bytes`], the text of the tokens
+/// borrows from the bytes.
+pub fn from_bytes(bytes: &[u8]) -> Result<Vec<Token<'_>>, DecodeError> {
+    let encoded = bytes.strip_prefix(MAGIC).ok_or(DecodeError::Version)?;
+    let (tokens, rest): (Vec<Encoded>, _) =
+        postcard::take_from_bytes(encoded).map_err(DecodeError::Invalid)?;
+    if !rest.is_empty() {
+        return Err(DecodeError::TrailingBytes(bytes.len() - rest.len()));
+    }
+    Ok(tokens.into_iter().map(Token::from).collect())
+}
+
+/// [`Token`] as it's encoded. The JSON of [`Token`] is adjacently tagged,
+/// which postcard can't decode, so the binary format has its own enum.
+#[derive(Serialize, Deserialize)]
+enum Encoded<'a> {
+    Metadata {
+        key: &'a str,
+        value: &'a str,
+    },
+    Ingredient {
+        name: &'a str,
+        #[serde(borrow)]
+        amounts: Vec<EncodedAmount<'a>>,
+    },
+    RecipeRef {
+        name: &'a str,
+        #[serde(borrow)]
+        amounts: Vec<EncodedAmount<'a>>,
+    },
+    Timer {
+        duration: &'a str,
+        kind: TimerKind,
+    },
+    Material(&'a str),
+    Word(&'a str),
+    Space(&'a str),
+    Comment(&'a str),
+    Backstory(&'a str),
+    Section(&'a str),
+    Link {
+        text: &'a str,
+        url: &'a str,
+    },
+    Image {
+        alt: &'a str,
+        url: &'a str,
+    },
+}
+
+/// [`Amount`] as it's encoded, its text always borrows from the bytes
+#[derive(Serialize, Deserialize)]
+struct EncodedAmount<'a> {
+    #[serde(borrow)]
+    quantity: Option<&'a str>,
+    #[serde(borrow)]
+    unit: Option<&'a str>,
+}
+
+fn encode_amounts<'a>(amounts: &'a [Amount]) -> Vec<EncodedAmount<'a>> {
+    amounts
+        .iter()
+        .map(|amount| EncodedAmount {
+            quantity: amount.quantity,
+            unit: amount.unit.as_deref(),
+        })
+        .collect()
+}
+
+fn decode_amounts(amounts: Vec<EncodedAmount<'_>>) -> Vec<Amount<'_>> {
+    amounts
+        .into_iter()
+        .map(|amount| Amount::new(amount.quantity, amount.unit))
+        .collect()
+}
+
+impl<'a> From<&'a Token<'_>> for Encoded<'a> {
+    fn from(token: &'a Token<'_>) -> Self {
+        match token {
+            Token::Metadata { key, value } => Encoded::Metadata { key, value },
+            Token::Ingredient { name, amounts } => Encoded::Ingredient {
+                name,
+                amounts: encode_amounts(amounts),
+            },
+            Token::RecipeRef { name, amounts } => Encoded::RecipeRef {
+                name,
+                amounts: encode_amounts(amounts),
+            },
+            Token::Timer { duration, kind } => Encoded::Timer {
+                duration,
+                kind: *kind,
+            },
+            Token::Material(value) => Encoded::Material(value),
+            Token::Word(value) => Encoded::Word(value),
+            Token::Space(value) => Encoded::Space(value),
+            Token::Comment(value) => Encoded::Comment(value),
+            Token::Backstory(value) => Encoded::Backstory(value),
+            Token::Section(value) => Encoded::Section(value),
+            Token::Link { text, url } => Encoded::Link { text, url },
+            Token::Image { alt, url } => Encoded::Image { alt, url },
+        }
+    }
+}
+
+impl<'a> From<Encoded<'a>> for Token<'a> {
+    fn from(token: Encoded<'a>) -> Self {
+        match token {
+            Encoded::Metadata { key, value } => Token::Metadata { key, value },
+            Encoded::Ingredient { name, amounts } => Token::Ingredient {
+                name: Cow::Borrowed(name),
+                amounts: decode_amounts(amounts),
+            },
+            Encoded::RecipeRef { name, amounts } => Token::RecipeRef {
+                name,
+                amounts: decode_amounts(amounts),
+            },
+            Encoded::Timer { duration, kind } => Token::Timer { duration, kind },
+            Encoded::Material(value) => Token::Material(value),
+            Encoded::Word(value) => Token::Word(value),
+            Encoded::Space(value) => Token::Space(value),
+            Encoded::Comment(value) => Token::Comment(value),
+            Encoded::Backstory(value) => Token::Backstory(value),
+            Encoded::Section(value) => Token::Section(value),
+            Encoded::Link { text, url } => Token::Link { text, url },
+            Encoded::Image { alt, url } => Token::Image { alt, url },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use alloc::vec;
+    use rstest::*;
+
+    #[rstest]
+    #[case("")]
+    #[case(">> name: soup\n>>> notes: a\nline\n\n= Dough\nMix {flour}(500 gr|1 lb) and {eggs}(3)")]
+    #[case("Heat the &{pot} for t{5 min} /* or more */ // hot\n@{../sauce}(2 cups)")]
+    #[case("Stir t{5 min}!active and rest t{1 h}!passive")]
+    #[case("See [this](https://example.com) ![img](img.png) café {salt}()\n---\nstory")]
+    fn test_binary_round_trip(#[case] input: &str) {
+        let tokens = parse(input).unwrap();
+        let bytes = to_bytes(&tokens);
+        assert_eq!(from_bytes(&bytes), Ok(tokens));
+    }
+
+    #[test]
+    fn test_binary_long_text() {
+        let word = "a".repeat(300);
+        let tokens = vec![Token::Word(&word)];
+        let bytes = to_bytes(&tokens);
+        assert_eq!(&bytes[MAGIC.len()..MAGIC.len() + 4], &[1, 5, 0xac, 0x02]);
+        assert_eq!(from_bytes(&bytes), Ok(tokens));
+    }
+
+    #[rstest]
+    #[case(b"rcp\x02\x00")]
+    #[case(b"rcp\x00\x00")]
+    #[case(b"{salt}")]
+    #[case(b"rcp")]
+    #[case(b"")]
+    fn test_binary_other_version(#[case] bytes: &[u8]) {
+        assert_eq!(from_bytes(bytes), Err(DecodeError::Version));
+    }
+
+    #[rstest]
+    #[case(b"rcp\x01")]
+    #[case(b"rcp\x01\x01\x0c")]
+    #[case(b"rcp\x01\x01\x05\x04ab")]
+    #[case(b"rcp\x01\x01\x05\x02\xff\xfe")]
+    #[case(b"rcp\x01\x01\x03\x01a\x02")]
+    #[case(b"rcp\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01")]
+    fn test_binary_invalid(#[case] bytes: &[u8]) {
+        assert!(matches!(from_bytes(bytes), Err(DecodeError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_binary_borrows_the_bytes() {
+        let bytes = to_bytes(&parse("{salt}(1 pinch)").unwrap());
+        let tokens = from_bytes(&bytes).unwrap();
+        let Token::Ingredient { name, amounts } = &tokens[0] else {
+            panic!("expected an ingredient, got {:?}", tokens[0]);
+        };
+        assert!(matches!(name, Cow::Borrowed("salt")));
+        assert!(matches!(amounts[0].unit, Some(Cow::Borrowed("pinch"))));
+    }
+
+    #[test]
+    fn test_binary_trailing_bytes() {
+        assert_eq!(
+            from_bytes(b"rcp\x01\x00\x00"),
+            Err(DecodeError::TrailingBytes(5))
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::parser::{is_valid_char, is_valid_quantity};
+
+/// Build the source of a recipe programmatically
+///
+/// Free text is escaped, so it's never interpreted as markup. Names can only
+/// contain the characters allowed inside curly braces, the rest are dropped.
+///
+/// Example:
+///
+/// ```
+/// use recipe_parser::RecipeBuilder;
+///
+/// let recipe = RecipeBuilder::new()
+///     .metadata("name", "boiled potatoes")
+///     .step("Boil the")
+///     .ingredient("potatoes", Some("3"), None)
+///     .text("for")
+///     .timer("20 minutes")
+///     .build();
+///
+/// assert_eq!(
+///     recipe,
+///     ">> name: boiled potatoes\n\nBoil the {potatoes}(3) for t{20 minutes}\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecipeBuilder {
+    metadata: Vec<(String, String)>,
+    steps: Vec<Vec<String>>,
+    backstory: Option<String>,
+}
+
+impl RecipeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a `>> key: value` line
+    pub fn metadata(mut self, key: &str, value: &str) -> Self {
+        let key = key.replace([':', '\n', '\r'], " ");
+        let value = value.replace(['\n', '\r'], " ");
+        self.metadata
+            .push((key.trim().to_string(), value.trim().to_string()));
+        self
+    }
+
+    /// Start a new step with the given text
+    pub fn step(mut self, text: &str) -> Self {
+        self.steps.push(vec![]);
+        self.text(text)
+    }
+
+    /// Append text to the current step
+    pub fn text(self, text: &str) -> Self {
+        let text = escape_text(text);
+        self.push(text)
+    }
+
+    /// Append an ingredient to the current step.
+    ///
+    /// The amount is only written when the quantity is valid, a unit
+    /// without a quantity is dropped. A `/` in the unit becomes a space,
+    /// it would start an alternative amount.
+    pub fn ingredient(self, name: &str, quantity: Option<&str>, unit: Option<&str>) -> Self {
+        let Some(name) = sanitize_name(name) else {
+            return self;
+        };
+        let amount = match (quantity.map(str::trim), unit.and_then(sanitize_unit)) {
+            (Some(quantity), Some(unit)) if is_valid_quantity(quantity) => {
+                format!("({quantity} {unit})")
+            }
+            (Some(quantity), None) if is_valid_quantity(quantity) => format!("({quantity})"),
+            _ => String::new(),
+        };
+        self.push(format!("{{{name}}}{amount}"))
+    }
+
+    /// Append a material to the current step
+    pub fn material(self, name: &str) -> Self {
+        match sanitize_name(name) {
+            Some(name) => self.push(format!("&{{{name}}}")),
+            None => self,
+        }
+    }
+
+    /// Append a timer to the current step
+    pub fn timer(self, duration: &str) -> Self {
+        match sanitize_name(duration) {
+            Some(duration) => self.push(format!("t{{{duration}}}")),
+            None => self,
+        }
+    }
+
+    /// Append a timer the cook doesn't need to attend, like resting, to
+    /// the current step
+    pub fn passive_timer(self, duration: &str) -> Self {
+        match sanitize_name(duration) {
+            Some(duration) => self.push(format!("t{{{duration}}}!passive")),
+            None => self,
+        }
+    }
+
+    /// Set the backstory, which is written at the end of the recipe
+    pub fn backstory(mut self, text: &str) -> Self {
+        self.backstory = Some(text.trim().to_string());
+        self
+    }
+
+    /// Write the recipe source
+    pub fn build(&self) -> String {
+        let mut out = String::new();
+        for (key, value) in &self.metadata {
+            out.push_str(&format!(">> {key}: {value}\n"));
+        }
+        if !self.metadata.is_empty() && !self.steps.is_empty() {
+            out.push('\n');
+        }
+        for step in &self.steps {
+            out.push_str(&step.join(" "));
+            out.push('\n');
+        }
+        if let Some(backstory) = &self.backstory {
+            out.push_str("\n---\n\n");
+            out.push_str(backstory);
+            out.push('\n');
+        }
+        out
+    }
+
+    fn push(mut self, fragment: String) -> Self {
+        if fragment.is_empty() {
+            return self;
+        }
+        match self.steps.last_mut() {
+            Some(step) => step.push(fragment),
+            None => self.steps.push(vec![fragment]),
+        }
+        self
+    }
+}
+
+fn sanitize_name(name: &str) -> Option<String> {
+    let name: String = name
+        .chars()
+        .map(|c| if c == '\n' || c == '\r' { ' ' } else { c })
+        .filter(|c| is_valid_char(*c))
+        .collect();
+    let name = name.trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Like [`sanitize_name`], and without `/` that separates alternative amounts
+fn sanitize_unit(unit: &str) -> Option<String> {
+    sanitize_name(&unit.replace('/', " "))
+}
+
+/// Escape the symbols that would start a markup at the beginning of a word,
+/// or of a line for sections
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let chars: Vec<char> = text.trim().chars().collect();
+    for (i, c) in chars.iter().enumerate() {
+        let word_start = i == 0 || chars[i - 1].is_whitespace();
+        let line_start = chars[..i]
+            .iter()
+            .rev()
+            .find(|c| !matches!(c, ' ' | '\t'))
+            .is_none_or(|c| *c == '\n' || *c == '\r');
+        let next = chars.get(i + 1).copied();
+        let escape = match c {
+            '\\' | '{' | '}' | '[' => true,
+            '=' => line_start,
+            '>' => word_start && next == Some('>'),
+            '/' => word_start && matches!(next, Some('/') | Some('*')),
+            '-' => word_start && next == Some('-'),
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(*c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("plain text", "plain text")]
+    #[case("use {braces}", "use \\{braces\\}")]
+    #[case(">> not: metadata", "\\>> not: metadata")]
+    #[case("a // comment", "a \\// comment")]
+    #[case("a /* comment */", "a \\/* comment */")]
+    #[case("https://example.com", "https://example.com")]
+    #[case("---", "\\---")]
+    #[case("well-done", "well-done")]
+    #[case("back\\slash", "back\\\\slash")]
+    #[case("see [this](url)", "see \\[this](url)")]
+    #[case("= not a section", "\\= not a section")]
+    #[case("2 = 1 + 1", "2 = 1 + 1")]
+    #[case("line1\n= head", "line1\n\\= head")]
+    #[case("line1\r\n  == head ==", "line1\r\n  \\== head ==")]
+    #[case("a\n2 = 1 + 1", "a\n2 = 1 + 1")]
+    fn test_escape_text(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(escape_text(input), expected);
+    }
+
+    #[test]
+    fn test_builder_drops_invalid_amounts() {
+        let recipe = RecipeBuilder::new()
+            .ingredient("salt", None, Some("pinch"))
+            .ingredient("pepper", Some("2."), None)
+            .ingredient("{oil}", Some("1"), Some("tbsp"))
+            .build();
+        assert_eq!(recipe, "{salt} {pepper} {oil}(1 tbsp)\n");
+    }
+
+    #[rstest]
+    #[case("line1\n= head")]
+    #[case("line1\n  == head ==\nline3")]
+    #[case("line1\r\n= head")]
+    #[case("line1\n>> key: value")]
+    #[case("line1\n---\nstory")]
+    #[case("= head\n\t= head")]
+    fn test_builder_text_stays_text(#[case] text: &str) {
+        use crate::{Token, parse};
+
+        let source = RecipeBuilder::new().step(text).build();
+        let tokens = parse(&source).unwrap();
+        assert!(
+            tokens
+                .iter()
+                .all(|token| matches!(token, Token::Word(_) | Token::Space(_))),
+            "{tokens:?}"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_builder_round_trip() {
+        use crate::{Amount, RenderOptions, TimerKind, Token, parse, render_text};
+
+        let source = RecipeBuilder::new()
+            .metadata("name", "tricky\nsoup")
+            .metadata("tags: extra", "vegan")
+            .step("Add the")
+            .ingredient("salt & pepper", Some("1/2"), Some("tsp"))
+            .text("and")
+            .ingredient("flour", Some("1"), Some("cup/2"))
+            .text("and {not an ingredient} // not a comment")
+            .step("Stir with a")
+            .material("wooden spoon")
+            .text("for")
+            .timer("5 minutes")
+            .text("then rest")
+            .passive_timer("1 hour")
+            .step("---")
+            .backstory("My grandma\n---\nsaid so")
+            .build();
+        println!("{source}");
+
+        let tokens = parse(&source).expect("failed to parse the built recipe");
+        let metadata: Vec<_> = tokens
+            .iter()
+            .filter_map(|t| match t {
+                Token::Metadata { key, value } => Some((*key, *value)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            metadata,
+            vec![("name", "tricky soup"), ("tags  extra", "vegan")]
+        );
+        assert!(tokens.contains(&Token::Ingredient {
+            name: "salt & pepper".into(),
+            amounts: vec![Amount::new(Some("1/2"), Some("tsp"))],
+        }));
+        assert!(tokens.contains(&Token::Ingredient {
+            name: "flour".into(),
+            amounts: vec![Amount::new(Some("1"), Some("cup 2"))],
+        }));
+        assert!(tokens.contains(&Token::Material("wooden spoon")));
+        assert!(tokens.contains(&Token::Timer {
+            duration: "5 minutes",
+            kind: TimerKind::Active
+        }));
+        assert!(tokens.contains(&Token::Timer {
+            duration: "1 hour",
+            kind: TimerKind::Passive
+        }));
+        assert!(tokens.contains(&Token::Backstory("My grandma\n---\nsaid so\n")));
+
+        let steps: Vec<Token> = tokens
+            .into_iter()
+            .filter(|t| !matches!(t, Token::Backstory(_)))
+            .collect();
+        let options = RenderOptions {
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_text(&steps, &options),
+            "Add the salt & pepper and flour and {not an ingredient} // not a comment\nStir with a wooden spoon for 5 minutes then rest 1 hour\n---"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeSet;
+
+use crate::aggregate::normalize_name;
+use crate::{Recipe, display_amounts};
+
+/// Semantic differences between two versions of a recipe
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RecipeDiff {
+    /// Ingredients in the order they appear, the removed ones last
+    pub ingredients: Vec<IngredientChange>,
+    pub added_steps: Vec<String>,
+    pub removed_steps: Vec<String>,
+    /// Metadata changes sorted by key
+    pub metadata: Vec<MetadataChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "change"))]
+pub enum IngredientChange {
+    Added {
+        name: String,
+        amounts: Vec<String>,
+    },
+    Removed {
+        name: String,
+        amounts: Vec<String>,
+    },
+    /// The ingredient is in both versions, with different amounts
+    Changed {
+        name: String,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
+}
+
+/// A metadata key that was added (`old` is `None`), removed (`new` is `None`)
+/// or changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MetadataChange {
+    pub key: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl RecipeDiff {
+    /// True when both versions are the same
+    pub fn is_empty(&self) -> bool {
+        self.ingredients.is_empty()
+            && self.added_steps.is_empty()
+            && self.removed_steps.is_empty()
+            && self.metadata.is_empty()
+    }
+}
+
+/// Amounts of every occurrence of each ingredient, grouped by normalized name
+fn ingredient_amounts(recipe: &Recipe) -> Vec<(String, String, Vec<String>)> {
+    let mut ingredients: Vec<(String, String, Vec<String>)> = vec![];
+    for ingredient in &recipe.ingredients {
+        let key = normalize_name(&ingredient.name);
+        let amounts = display_amounts(&ingredient.amounts);
+        match ingredients.iter_mut().find(|(k, _, _)| *k == key) {
+            Some((_, _, all)) => all.push(amounts),
+            None => ingredients.push((key, ingredient.name.trim().to_string(), vec![amounts])),
+        }
+    }
+    for (_, _, amounts) in &mut ingredients {
+        amounts.retain(|amount| !amount.is_empty());
+    }
+    ingredients
+}
+
+/// Compare two versions of a recipe.
+///
+/// Ingredients are matched by name, ignoring case and surrounding spaces,
+/// so moving an ingredient to another step is not a change. Steps are
+/// compared by their text.
+///
+/// ```
+/// use recipe_parser::{IngredientChange, Recipe, diff};
+///
+/// let old = Recipe::try_from("Boil the {potatoes}(3)").unwrap();
+/// let new = Recipe::try_from("Boil the {potatoes}(4)").unwrap();
+/// let changes = diff(&old, &new);
+/// assert_eq!(
+///     changes.ingredients,
+///     vec![IngredientChange::Changed {
+///         name: "potatoes".to_string(),
+///         old: vec!["3".to_string()],
+///         new: vec!["4".to_string()],
+///     }]
+/// );
+/// ```
+pub fn diff(old: &Recipe, new: &Recipe) -> RecipeDiff {
+    let old_ingredients = ingredient_amounts(old);
+    let new_ingredients = ingredient_amounts(new);
+
+    let mut ingredients = vec![];
+    for (key, name, amounts) in &new_ingredients {
+        match old_ingredients.iter().find(|(k, _, _)| k == key) {
+            None => ingredients.push(IngredientChange::Added {
+                name: name.clone(),
+                amounts: amounts.clone(),
+            }),
+            Some((_, _, old_amounts)) if old_amounts != amounts => {
+                ingredients.push(IngredientChange::Changed {
+                    name: name.clone(),
+                    old: old_amounts.clone(),
+                    new: amounts.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, name, amounts) in &old_ingredients {
+        if !new_ingredients.iter().any(|(k, _, _)| k == key) {
+            ingredients.push(IngredientChange::Removed {
+                name: name.clone(),
+                amounts: amounts.clone(),
+            });
+        }
+    }
+
+    let old_steps = old.steps();
+    let new_steps = new.steps();
+    let added_steps = new_steps
+        .iter()
+        .filter(|step| !old_steps.contains(step))
+        .cloned()
+        .collect();
+    let removed_steps = old_steps
+        .iter()
+        .filter(|step| !new_steps.contains(step))
+        .cloned()
+        .collect();
+
+    let keys: BTreeSet<&String> = old.metadata.keys().chain(new.metadata.keys()).collect();
+    let metadata = keys
+        .into_iter()
+        .filter_map(|key| {
+            let old = old.metadata.get(key);
+            let new = new.metadata.get(key);
+            (old != new).then(|| MetadataChange {
+                key: key.clone(),
+                old: old.cloned(),
+                new: new.cloned(),
+            })
+        })
+        .collect();
+
+    RecipeDiff {
+        ingredients,
+        added_steps,
+        removed_steps,
+        metadata,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diff_same_recipe_is_empty() {
+        let recipe = Recipe::try_from(">> name: soup\nBoil the {potatoes}(3)").unwrap();
+        assert!(diff(&recipe, &recipe).is_empty());
+    }
+
+    #[test]
+    fn test_diff_reordered_ingredients_is_not_a_change() {
+        let old = Recipe::try_from("Mix the {flour}(200 gr) and the {Sugar}(50 gr).").unwrap();
+        let new = Recipe::try_from("Mix the {sugar}(50 gr) and the {flour}(200 gr).").unwrap();
+        let changes = diff(&old, &new);
+        assert!(changes.ingredients.is_empty());
+        assert_eq!(
+            changes.added_steps,
+            vec!["Mix the sugar and the flour.".to_string()]
+        );
+        assert_eq!(
+            changes.removed_steps,
+            vec!["Mix the flour and the Sugar.".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_diff_ingredients_and_metadata() {
+        let old = Recipe::try_from(
+            ">> name: soup\n>> servings: 2\nBoil the {potatoes}(3) with {salt}.\nServe.",
+        )
+        .unwrap();
+        let new = Recipe::try_from(
+            ">> name: soup\n>> servings: 4\n>> tags: easy\nBoil the {potatoes}(6) with {pepper}.\nServe.",
+        )
+        .unwrap();
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes.ingredients,
+            vec![
+                IngredientChange::Changed {
+                    name: "potatoes".to_string(),
+                    old: vec!["3".to_string()],
+                    new: vec!["6".to_string()],
+                },
+                IngredientChange::Added {
+                    name: "pepper".to_string(),
+                    amounts: vec![],
+                },
+                IngredientChange::Removed {
+                    name: "salt".to_string(),
+                    amounts: vec![],
+                },
+            ]
+        );
+        assert_eq!(
+            changes.metadata,
+            vec![
+                MetadataChange {
+                    key: "servings".to_string(),
+                    old: Some("2".to_string()),
+                    new: Some("4".to_string()),
+                },
+                MetadataChange {
+                    key: "tags".to_string(),
+                    old: None,
+                    new: Some("easy".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            changes.added_steps,
+            vec!["Boil the potatoes with pepper.".to_string()]
+        );
+        assert_eq!(
+            changes.removed_steps,
+            vec!["Boil the potatoes with salt.".to_string()]
+        );
+    }
+}
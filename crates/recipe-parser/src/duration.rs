@@ -0,0 +1,145 @@
+use core::time::Duration;
+
+use crate::Quantity;
+
+/// Parse the natural-language duration of a timer, like `25 minutes`,
+/// `1 hour 30 min` or `1/2 h`.
+///
+/// ISO-8601 durations, like `PT1H30M`, are also understood, without years
+/// and months since their length varies.
+///
+/// Returns `None` when the value doesn't look like a duration.
+///
+/// ```
+/// use std::time::Duration;
+/// use recipe_parser::parse_duration;
+///
+/// assert_eq!(parse_duration("1h 30 min"), Some(Duration::from_secs(90 * 60)));
+/// assert_eq!(parse_duration("PT1H30M"), Some(Duration::from_secs(90 * 60)));
+/// ```
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    if let Some(designators) = value.trim().strip_prefix(['P', 'p']) {
+        return parse_iso8601(designators);
+    }
+    let mut total = Duration::ZERO;
+    let mut parts = 0;
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || ".,/_".contains(c)))
+            .unwrap_or(rest.len());
+        let quantity = Quantity::parse(&rest[..number_len])?;
+        rest = rest[number_len..].trim_start();
+        let unit_len = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let seconds = unit_seconds(&rest[..unit_len])?;
+        rest = rest[unit_len..].trim_start_matches([' ', ',']);
+        rest = rest.strip_prefix("and ").unwrap_or(rest);
+
+        let seconds = quantity.checked_mul(Quantity::new(seconds, 1)?)?.to_f64();
+        if seconds < 0.0 {
+            return None;
+        }
+        total = total.checked_add(Duration::try_from_secs_f64(seconds).ok()?)?;
+        parts += 1;
+    }
+    if parts == 0 { None } else { Some(total) }
+}
+
+/// The part of an ISO-8601 duration after the `P`, like `1DT2H30M`
+fn parse_iso8601(designators: &str) -> Option<Duration> {
+    if designators.is_empty() {
+        return None;
+    }
+    let (date, time) = match designators.split_once(['T', 't']) {
+        Some((date, time)) if !time.is_empty() => (date, time),
+        Some(_) => return None,
+        None => (designators, ""),
+    };
+    let mut total = Duration::ZERO;
+    for (part, units) in [(date, "WD"), (time, "HMS")] {
+        let mut units = units.chars();
+        let mut rest = part;
+        while !rest.is_empty() {
+            let number_len = rest.find(|c: char| !(c.is_ascii_digit() || ".,".contains(c)))?;
+            let unit = rest[number_len..].chars().next()?.to_ascii_uppercase();
+            // Each designator at most once and in order
+            units.find(|expected| *expected == unit)?;
+            let seconds = match unit {
+                'W' => 7 * 24 * 60 * 60,
+                'D' => 24 * 60 * 60,
+                'H' => 60 * 60,
+                'M' => 60,
+                _ => 1,
+            };
+            let quantity = Quantity::parse(&rest[..number_len].replace(',', "."))?;
+            let seconds = quantity.checked_mul(Quantity::new(seconds, 1)?)?.to_f64();
+            total = total.checked_add(Duration::try_from_secs_f64(seconds).ok()?)?;
+            rest = &rest[number_len + 1..];
+        }
+    }
+    Some(total)
+}
+
+fn unit_seconds(unit: &str) -> Option<i64> {
+    let seconds = match unit.to_lowercase().as_str() {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60,
+        "d" | "day" | "days" => 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(seconds)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("25 minutes", 25 * 60)]
+    #[case("10 sec", 10)]
+    #[case("1 hour", 3600)]
+    #[case("2 Hours", 7200)]
+    #[case("1h30m", 90 * 60)]
+    #[case("1 hour 30 minutes", 90 * 60)]
+    #[case("1 hour, 30 minutes", 90 * 60)]
+    #[case("1 hour and 30 minutes", 90 * 60)]
+    #[case("1/2 hour", 30 * 60)]
+    #[case("1.5 h", 90 * 60)]
+    #[case("1 day", 24 * 3600)]
+    #[case("PT1H30M", 90 * 60)]
+    #[case("PT45S", 45)]
+    #[case("PT90M", 90 * 60)]
+    #[case("PT1.5H", 90 * 60)]
+    #[case("PT0,5H", 30 * 60)]
+    #[case("P1DT2H", 26 * 3600)]
+    #[case("P1W", 7 * 24 * 3600)]
+    #[case(" pt10m ", 10 * 60)]
+    fn test_parse_duration_ok(#[case] input: &str, #[case] seconds: u64) {
+        assert_eq!(parse_duration(input), Some(Duration::from_secs(seconds)));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("a while")]
+    #[case("5")]
+    #[case("5 parsecs")]
+    #[case("until golden")]
+    #[case("P")]
+    #[case("PT")]
+    #[case("P1DT")]
+    #[case("PT30")]
+    #[case("PTM")]
+    #[case("PT30M1H")]
+    #[case("PT1H1H")]
+    #[case("P1M")]
+    #[case("P1Y")]
+    #[case("PT1H 30M")]
+    #[case("Pasta")]
+    fn test_parse_duration_invalid(#[case] input: &str) {
+        assert_eq!(parse_duration(input), None);
+    }
+}
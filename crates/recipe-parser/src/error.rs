@@ -0,0 +1,171 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt::Display;
+
+use winnow::error::{StrContext, StrContextValue};
+
+use crate::Error;
+use crate::parser::SAME_LINE_CURLY;
+
+/// Error of a recipe that could not be parsed, independent of the parser input
+///
+/// Unclosed delimiters, like `{`, point at the opening delimiter instead of
+/// the end of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipeError {
+    /// Byte offset in the source
+    pub offset: usize,
+    pub message: String,
+}
+
+/// Delimiters that the parser expects closed, with their opening counterpart
+const DELIMITERS: &[(&str, &str)] = &[("}", "{"), (")", "("), ("*/", "/*")];
+
+impl RecipeError {
+    /// Line and column of the error, both starting at 1.
+    ///
+    /// The column counts characters, not bytes.
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        let offset = self.offset.min(source.len());
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        let column = before[line_start..].chars().count() + 1;
+        (line, column)
+    }
+
+    /// Show the line of the source where the error is, with a caret under
+    /// the problem.
+    ///
+    /// ```
+    /// use recipe_parser::{parse, RecipeError};
+    ///
+    /// let source = "this is an {invalid recipe";
+    /// let error = RecipeError::from(parse(source).unwrap_err());
+    /// assert_eq!(
+    ///     error.render(source),
+    ///     "error: unclosed `{`, expected `}`
+    ///  --> 1:12
+    ///   |
+    /// 1 | this is an {invalid recipe
+    ///   |            ^
+    /// "
+    /// );
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let (line, column) = self.line_column(source);
+        let text = source.lines().nth(line - 1).unwrap_or_default();
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+        // Tabs are kept so the caret lines up with the text
+        let padding: String = text
+            .chars()
+            .take(column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "error: {}\n{gutter}--> {line}:{column}\n{gutter} |\n{number} | {text}\n{gutter} | {padding}^\n",
+            self.message
+        )
+    }
+}
+
+impl From<Error<'_, '_>> for RecipeError {
+    fn from(err: Error<'_, '_>) -> Self {
+        let source: &str = err.input();
+        let offset = err.offset();
+        let expected = err.inner().context().find_map(|context| match context {
+            StrContext::Expected(StrContextValue::CharLiteral(c)) => Some(c.to_string()),
+            StrContext::Expected(StrContextValue::StringLiteral(s)) => Some(s.to_string()),
+            _ => None,
+        });
+        let multiline_name = err.inner().context().any(|context| {
+            *context == StrContext::Expected(StrContextValue::Description(SAME_LINE_CURLY))
+        });
+        if multiline_name && let Some(position) = source[..offset].rfind('{') {
+            return Self {
+                offset: position,
+                message: "unclosed `{`, names cannot span multiple lines".to_string(),
+            };
+        }
+        let unclosed = expected.as_deref().and_then(|expected| {
+            let (close, open) = DELIMITERS.iter().find(|(close, _)| *close == expected)?;
+            // When it's closed later, the problem is where the parser stopped
+            if source[offset..].contains(close) {
+                return None;
+            }
+            let position = source[..offset].rfind(open)?;
+            Some((position, format!("unclosed `{open}`, expected `{close}`")))
+        });
+        match unclosed {
+            Some((offset, message)) => Self { offset, message },
+            None => {
+                let message = err.inner().to_string();
+                Self {
+                    offset,
+                    message: if message.is_empty() {
+                        "invalid recipe".to_string()
+                    } else {
+                        message
+                    },
+                }
+            }
+        }
+    }
+}
+
+impl Display for RecipeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl core::error::Error for RecipeError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use rstest::*;
+
+    #[rstest]
+    #[case("this is an {invalid recipe", 11, "unclosed `{`, expected `}`")]
+    #[case("Boil {water}(1 l", 12, "unclosed `(`, expected `)`")]
+    #[case("Boil /* slowly", 5, "unclosed `/*`, expected `*/`")]
+    #[case(
+        "Boil {sweet\npotato}",
+        5,
+        "unclosed `{`, names cannot span multiple lines"
+    )]
+    #[case("Boil {{water}}", 6, "expected a name, curly braces cannot be nested")]
+    #[case(
+        "{water}(a lot)",
+        8,
+        "expected a quantity value, like 3, 1.2, 1/2 or 1_000"
+    )]
+    fn test_recipe_error_from_parse_error(
+        #[case] source: &str,
+        #[case] offset: usize,
+        #[case] message: &str,
+    ) {
+        let error = RecipeError::from(parse(source).unwrap_err());
+        assert_eq!(
+            error,
+            RecipeError {
+                offset,
+                message: message.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_recipe_error_render_multiline() {
+        let source = "Boil the {water}.\n\tAdd the {salt (1 pinch)}\nServe.";
+        let error = RecipeError::from(parse(source).unwrap_err());
+        assert_eq!(error.line_column(source), (2, 16));
+        assert_eq!(
+            error.render(source),
+            "error: expected `}`\n --> 2:16\n  |\n2 | \tAdd the {salt (1 pinch)}\n  | \t              ^\n"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::parser::parse_spanned_partial;
+use crate::{Amount, ParseOptions, RecipeError, Token, parse_spanned};
+
+/// Describe how the input is split into tokens, one line per token with
+/// its kind, its content and its byte range. Meant for debugging a recipe
+/// that doesn't come out as expected.
+///
+/// When the input is invalid, the tokens before the error are described
+/// and the last line is the error.
+///
+/// ```
+/// use recipe_parser::explain;
+///
+/// assert_eq!(
+///     explain("Cook {quinoa}(200 gr) in a &{pot}"),
+///     r#"Word("Cook") @ 0..4
+/// Space(" ") @ 4..5
+/// Ingredient(name="quinoa", amounts=["200 gr"]) @ 5..21
+/// Space(" ") @ 21..22
+/// Word("in") @ 22..24
+/// Space(" ") @ 24..25
+/// Word("a") @ 25..26
+/// Space(" ") @ 26..27
+/// Material("pot") @ 27..33
+/// "#
+/// );
+/// ```
+pub fn explain(input: &str) -> String {
+    let mut out = String::new();
+    for (token, span) in parse_spanned_partial(input, &ParseOptions::default()) {
+        match token {
+            Token::Metadata { key, value } => write!(out, "Metadata(key={key:?}, value={value:?})"),
+            Token::Ingredient { name, amounts } => {
+                write!(
+                    out,
+                    "Ingredient(name={name:?}{})",
+                    explain_amounts(&amounts)
+                )
+            }
+            Token::RecipeRef { name, amounts } => {
+                write!(out, "RecipeRef(name={name:?}{})", explain_amounts(&amounts))
+            }
+            Token::Timer { duration, kind } => write!(out, "Timer({duration:?}, {kind:?})"),
+            Token::Material(value) => write!(out, "Material({value:?})"),
+            Token::Word(value) => write!(out, "Word({value:?})"),
+            Token::Space(value) => write!(out, "Space({value:?})"),
+            Token::Comment(value) => write!(out, "Comment({value:?})"),
+            Token::Backstory(value) => write!(out, "Backstory({value:?})"),
+            Token::Section(value) => write!(out, "Section({value:?})"),
+            Token::Link { text, url } => write!(out, "Link(text={text:?}, url={url:?})"),
+            Token::Image { alt, url } => write!(out, "Image(alt={alt:?}, url={url:?})"),
+        }
+        .expect("writing to a String cannot fail");
+        writeln!(out, " @ {}..{}", span.start, span.end).expect("writing to a String cannot fail");
+    }
+    if let Err(err) = parse_spanned(input) {
+        let err = RecipeError::from(err);
+        writeln!(out, "error: {} @ {}", err.message, err.offset)
+            .expect("writing to a String cannot fail");
+    }
+    out
+}
+
+fn explain_amounts(amounts: &[Amount]) -> String {
+    if amounts.is_empty() {
+        return String::new();
+    }
+    let amounts: Vec<String> = amounts
+        .iter()
+        .map(|amount| format!("{:?}", amount.to_string()))
+        .collect();
+    format!(", amounts=[{}]", amounts.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("", "")]
+    #[case("{salt}", "Ingredient(name=\"salt\") @ 0..6\n")]
+    #[case(
+        "{butter}(113 gr / 1 stick)",
+        "Ingredient(name=\"butter\", amounts=[\"113 gr\", \"1 stick\"]) @ 0..26\n"
+    )]
+    #[case("t{5 min}", "Timer(\"5 min\", Active) @ 0..8\n")]
+    #[case("t{5 min}!passive", "Timer(\"5 min\", Passive) @ 0..16\n")]
+    #[case("at{5}", "Word(\"at{5}\") @ 0..5\n")]
+    #[case(
+        "@{sauce}(1 cup)",
+        "RecipeRef(name=\"sauce\", amounts=[\"1 cup\"]) @ 0..15\n"
+    )]
+    #[case(
+        ">> name: soup\n= Soup",
+        "Metadata(key=\"name\", value=\"soup\") @ 0..14\nSection(\"Soup\") @ 14..20\n"
+    )]
+    #[case("/* hot */", "Comment(\"hot\") @ 0..9\n")]
+    #[case(
+        "[fold](https://example.com)",
+        "Link(text=\"fold\", url=\"https://example.com\") @ 0..27\n"
+    )]
+    #[case("![img](img.png)", "Image(alt=\"img\", url=\"img.png\") @ 0..15\n")]
+    #[case("a\n---\nstory", "Word(\"a\") @ 0..1\nBackstory(\"story\") @ 1..11\n")]
+    fn test_explain(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(explain(input), expected);
+    }
+
+    #[test]
+    fn test_explain_invalid() {
+        assert_eq!(
+            explain("Add {salt"),
+            "Word(\"Add\") @ 0..3\nSpace(\" \") @ 3..4\nerror: unclosed `{`, expected `}` @ 4\n"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Grammar of recipe-lang, what [`parse`](crate::parse) accepts.
+//!
+//! The notation is [EBNF]: `{ x }` is zero or more, `{ x }-` one or more,
+//! `[ x ]` optional and `a - b` is `a` except `b`.
+//!
+//! ```ebnf
+//! recipe             = { token } ;
+//! token              = multiline metadata | metadata | section
+//!                    | material | timer | ingredient | recipe ref
+//!                    | backstory | comment | line comment
+//!                    | image | link | "(" | escaped | "\"
+//!                    | word | whitespace ;
+//!
+//! metadata           = line start, ">>", [ spaces ], key, ":", [ spaces ],
+//!                      { char - newline }, [ newline ] ;
+//! multiline metadata = line start, ">>>", [ spaces ], { char - ( ":" | "\n" ) }-, ":",
+//!                      [ spaces ], multiline value ;
+//! key                = { char - ":" }- ;
+//! multiline value    = { line } ;  (* till a line starting with ">>" or a "---" line *)
+//!
+//! section            = line start, { "=" }-, text ;  (* trailing "=" are dropped *)
+//!
+//! material           = "&", name ;
+//! timer              = "t", name, [ "!", ( "active" | "passive" ) ] ;
+//! ingredient         = name, [ amounts ] ;
+//! recipe ref         = "@", [ "recipe" ], name, [ amounts ] ;
+//! name               = "{", { name char }-, "}" ;  (* spaces around are trimmed *)
+//! name char          = letter | digit | " " | "\t" | "/" | "-" | "_" | "@" | "." | ","
+//!                    | "%" | "#" | "'" | "&" | "+" | "!" | ":" ;
+//!
+//! amounts            = "(", [ spaces ], ")"
+//!                    | "(", [ spaces ], amount, { [ spaces ], "/", [ spaces ], amount }, ")" ;
+//! amount             = quantity, [ [ spaces ], unit ] ;
+//! quantity           = [ digits ], { separator, digits } ;  (* not empty, nor followed by a separator *)
+//! separator          = "." | "," | "/" | "_" ;
+//! unit               = { name char - "/" }- ;
+//!
+//! backstory          = newline, [ whitespace ], "---", [ spaces ], ( newline | end ),
+//!                      [ whitespace ], { char } ;
+//! comment            = "/*", { char }, "*/", [ spaces ] ;
+//! line comment       = "//", { char - newline } ;
+//!
+//! link               = "[", { char - ( "]" | newline ) }-, "]",
+//!                      "(", { char - ( ")" | space | newline ) }-, ")" ;
+//! image              = "!", link ;
+//! escaped            = "\", ( "\" | "{" | "}" | ">" | "/" | "-" | "[" | "=" ) ;
+//!
+//! word               = { char - ( space | newline | "\" ) }- ;
+//! spaces             = { " " | "\t" }- ;
+//! whitespace         = { " " | "\t" | "\r" | "\n" }- ;
+//! newline            = "\n" | "\r\n" ;
+//! text               = { char - newline }- ;
+//! line start         = ? only spaces since the previous newline or the start ? ;
+//! ```
+//!
+//! The alternatives of `token` are tried in order and the first one that
+//! matches wins, which settles the ambiguities:
+//!
+//! - `&{pot}` and `t{5 minutes}` are a material and a timer, not a word
+//!   followed by an ingredient. `{}`, `&{}` and `t{}` are words.
+//! - A word runs till the next space, so `at{5 min}`, `warm{butter}` or
+//!   `x//y` are words, and `https://example.com` is not a comment. The `t`
+//!   and `&` prefixes only count at the start of a token.
+//! - `>>` and `=` only start metadata and sections at the start of a line.
+//! - `---` only starts the backstory alone in its line after another line.
+//! - Whitespace is taken as a whole run, two whitespace tokens are never
+//!   next to each other. The run stops before the newline of a backstory.
+//! - Amounts go right after the name, `{salt} (1)` is an ingredient and words.
+//! - A `/` inside a quantity is a fraction, `{sugar}(1/2)` is half. With
+//!   spaces around it separates alternative amounts, `{sugar}(1 / 2)` is 1
+//!   or 2. This changed with alternative amounts, `(1 / 2)` used to be the
+//!   quantity `1` with the unit `/ 2`.
+//!
+//! A few inputs are errors instead of words: an unclosed name like `{salt`,
+//! a name spanning lines, nested braces like `{{salt}}`, unclosed amounts
+//! like `{salt}(1` or `{salt}(pinch)`, without a quantity, and unclosed
+//! comments like `/* stir`.
+//!
+//! [`ParseOptions`](crate::ParseOptions) can turn off line comments and
+//! escapes, allow extra characters in names, change the `&` and `t`
+//! prefixes of materials and timers, and allow sums and products in
+//! quantities.
+//!
+//! [EBNF]: https://en.wikipedia.org/wiki/Extended_Backus%E2%80%93Naur_form
+
+#[cfg(test)]
+mod test {
+    use crate::{Amount, TimerKind, Token, parse};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use rstest::*;
+
+    fn ingredient<'a>(name: &'a str, amounts: Vec<Amount<'a>>) -> Token<'a> {
+        Token::Ingredient {
+            name: name.into(),
+            amounts,
+        }
+    }
+
+    fn timer(duration: &str) -> Token<'_> {
+        Token::Timer {
+            duration,
+            kind: TimerKind::Active,
+        }
+    }
+
+    fn metadata<'a>(key: &'a str, value: &'a str) -> Token<'a> {
+        Token::Metadata { key, value }
+    }
+
+    // One case for each production of the grammar
+    #[rstest]
+    #[case(">> tags: vegan", vec![metadata("tags", "vegan")])]
+    #[case(">>tags:vegan\nGo", vec![metadata("tags", "vegan"), Token::Word("Go")])]
+    #[case(">>> notes: a\nb", vec![metadata("notes", "a\nb")])]
+    #[case("= Dough ==", vec![Token::Section("Dough")])]
+    #[case("&{pot}", vec![Token::Material("pot")])]
+    #[case("t{5 minutes}", vec![timer("5 minutes")])]
+    #[case("t{5 minutes}!passive", vec![Token::Timer { duration: "5 minutes", kind: TimerKind::Passive }])]
+    #[case("{salt}", vec![ingredient("salt", vec![])])]
+    #[case("{ salt }()", vec![ingredient("salt", vec![])])]
+    #[case("{salt}(1 pinch)", vec![ingredient("salt", vec![Amount::new(Some("1"), Some("pinch"))])])]
+    #[case("{salt}(1)", vec![ingredient("salt", vec![Amount::new(Some("1"), None)])])]
+    #[case("{butter}(113gr / 1/2 stick)", vec![ingredient("butter", vec![Amount::new(Some("113"), Some("gr")), Amount::new(Some("1/2"), Some("stick"))])])]
+    #[case("{flour}(1_000.5 gr)", vec![ingredient("flour", vec![Amount::new(Some("1_000.5"), Some("gr"))])])]
+    #[case("{a-b_c@d.e,f%g#h'i&j+k!l:m}", vec![ingredient("a-b_c@d.e,f%g#h'i&j+k!l:m", vec![])])]
+    #[case("@{woile/sauce}(1 cup)", vec![Token::RecipeRef { name: "woile/sauce", amounts: vec![Amount::new(Some("1"), Some("cup"))] }])]
+    #[case("Go\n---\nstory", vec![Token::Word("Go"), Token::Backstory("story")])]
+    #[case("/* stir */ ", vec![Token::Comment("stir")])]
+    #[case("/* {salt}\n&{pot} */", vec![Token::Comment("{salt}\n&{pot}")])]
+    #[case("// taste", vec![Token::Comment("taste")])]
+    #[case("[this](a.html)", vec![Token::Link { text: "this", url: "a.html" }])]
+    #[case("![dish](a.png)", vec![Token::Image { alt: "dish", url: "a.png" }])]
+    #[case("(", vec![Token::Word("(")])]
+    #[case("\\{", vec![Token::Word("{")])]
+    #[case("\\q", vec![Token::Word("\\"), Token::Word("q")])]
+    #[case("boil,", vec![Token::Word("boil,")])]
+    #[case(" \t", vec![Token::Space(" \t")])]
+    #[case("\r\n\n", vec![Token::Space("\r\n\n")])]
+    #[case("", vec![])]
+    fn test_grammar_productions(#[case] input: &str, #[case] expected: Vec<Token>) {
+        assert_eq!(parse(input).unwrap(), expected);
+    }
+
+    // How the order of the alternatives settles the ambiguities
+    #[rstest]
+    #[case("{}", vec![Token::Word("{}")])]
+    #[case("&{}", vec![Token::Word("&{}")])]
+    #[case("t{}", vec![Token::Word("t{}")])]
+    #[case("@{}", vec![Token::Word("@{}")])]
+    #[case("@recipe{}", vec![Token::Word("@recipe{}")])]
+    #[case("@recipes{dough}", vec![Token::Word("@recipes{dough}")])]
+    #[case("&{pot}(1)", vec![Token::Material("pot"), Token::Word("("), Token::Word("1)")])]
+    #[case("t{5}(1)", vec![timer("5"), Token::Word("("), Token::Word("1)")])]
+    #[case("at{5}", vec![Token::Word("at{5}")])]
+    #[case("the {salt}", vec![Token::Word("the"), Token::Space(" "), ingredient("salt", vec![])])]
+    #[case("x{salt}", vec![Token::Word("x{salt}")])]
+    #[case("{salt} (1)", vec![ingredient("salt", vec![]), Token::Space(" "), Token::Word("("), Token::Word("1)")])]
+    #[case("{salt}.", vec![ingredient("salt", vec![]), Token::Word(".")])]
+    #[case("x//y", vec![Token::Word("x//y")])]
+    #[case("https://example.com", vec![Token::Word("https://example.com")])]
+    #[case("a/b", vec![Token::Word("a/b")])]
+    #[case("[x](y z)", vec![Token::Word("[x](y"), Token::Space(" "), Token::Word("z)")])]
+    #[case("!x", vec![Token::Word("!x")])]
+    fn test_grammar_token_order(#[case] input: &str, #[case] expected: Vec<Token>) {
+        assert_eq!(parse(input).unwrap(), expected);
+    }
+
+    // Materials and timers only start a token, the `t` or `&` at the end
+    // of a word is part of it
+    #[rstest]
+    #[case("warm{butter}", vec![Token::Word("warm{butter}")])]
+    #[case("heat{butter}", vec![Token::Word("heat{butter}")])]
+    #[case("heat&{pot}", vec![Token::Word("heat&{pot}")])]
+    #[case("warm {butter}", vec![Token::Word("warm"), Token::Space(" "), ingredient("butter", vec![])])]
+    #[case("heat {butter}", vec![Token::Word("heat"), Token::Space(" "), ingredient("butter", vec![])])]
+    #[case("wait t{5 min}", vec![Token::Word("wait"), Token::Space(" "), timer("5 min")])]
+    #[case("heat\t&{pot}", vec![Token::Word("heat"), Token::Space("\t"), Token::Material("pot")])]
+    #[case("{salt}t{5 min}", vec![ingredient("salt", vec![]), timer("5 min")])]
+    #[case("{salt}&{pot}", vec![ingredient("salt", vec![]), Token::Material("pot")])]
+    #[case("(t{5 min})", vec![Token::Word("("), timer("5 min"), Token::Word(")")])]
+    #[case("at\nt{5 min}", vec![Token::Word("at"), Token::Space("\n"), timer("5 min")])]
+    fn test_grammar_prefix_boundary(#[case] input: &str, #[case] expected: Vec<Token>) {
+        assert_eq!(parse(input).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("  >> k: v", vec![Token::Space("  "), metadata("k", "v")])]
+    #[case("a\n>> k: v", vec![Token::Word("a"), Token::Space("\n"), metadata("k", "v")])]
+    #[case("a\n\t>>> k: v", vec![Token::Word("a"), Token::Space("\n\t"), metadata("k", "v")])]
+    #[case("Serve >> hot: yes", vec![Token::Word("Serve"), Token::Space(" "), Token::Word(">>"), Token::Space(" "), Token::Word("hot:"), Token::Space(" "), Token::Word("yes")])]
+    #[case("a >>> k: v", vec![Token::Word("a"), Token::Space(" "), Token::Word(">>>"), Token::Space(" "), Token::Word("k:"), Token::Space(" "), Token::Word("v")])]
+    #[case("\n = Dough", vec![Token::Space("\n "), Token::Section("Dough")])]
+    #[case("a = b", vec![Token::Word("a"), Token::Space(" "), Token::Word("="), Token::Space(" "), Token::Word("b")])]
+    #[case("===", vec![Token::Word("===")])]
+    fn test_grammar_line_start(#[case] input: &str, #[case] expected: Vec<Token>) {
+        assert_eq!(parse(input).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("a\n---\nb", vec![Token::Word("a"), Token::Backstory("b")])]
+    #[case("a\r\n---\r\nb", vec![Token::Word("a"), Token::Backstory("b")])]
+    #[case("a\n\n  --- \n\nb", vec![Token::Word("a"), Token::Backstory("b")])]
+    #[case("a\n---", vec![Token::Word("a"), Token::Backstory("")])]
+    #[case("---\nb", vec![Token::Word("---"), Token::Space("\n"), Token::Word("b")])]
+    #[case("a ---\nb", vec![Token::Word("a"), Token::Space(" "), Token::Word("---"), Token::Space("\n"), Token::Word("b")])]
+    #[case("a\n---b", vec![Token::Word("a"), Token::Space("\n"), Token::Word("---b")])]
+    #[case("a\n--- b", vec![Token::Word("a"), Token::Space("\n"), Token::Word("---"), Token::Space(" "), Token::Word("b")])]
+    #[case("a\n----\nb", vec![Token::Word("a"), Token::Space("\n"), Token::Word("----"), Token::Space("\n"), Token::Word("b")])]
+    #[case("a\n\\---\nb", vec![Token::Word("a"), Token::Space("\n"), Token::Word("-"), Token::Word("--"), Token::Space("\n"), Token::Word("b")])]
+    #[case("a\n---\n{salt}\n---\nc", vec![Token::Word("a"), Token::Backstory("{salt}\n---\nc")])]
+    fn test_grammar_backstory(#[case] input: &str, #[case] expected: Vec<Token>) {
+        assert_eq!(parse(input).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("{sugar}(1/2)", vec![Amount::new(Some("1/2"), None)])]
+    #[case("{sugar}(1/2 cup)", vec![Amount::new(Some("1/2"), Some("cup"))])]
+    #[case("{sugar}(1 / 2)", vec![Amount::new(Some("1"), None), Amount::new(Some("2"), None)])]
+    #[case("{sugar}(1 /2)", vec![Amount::new(Some("1"), None), Amount::new(Some("2"), None)])]
+    fn test_grammar_fraction_or_alternatives(#[case] input: &str, #[case] amounts: Vec<Amount>) {
+        assert_eq!(parse(input).unwrap(), vec![ingredient("sugar", amounts)]);
+    }
+
+    #[rstest]
+    #[case("{salt")]
+    #[case("{sweet\npotato}")]
+    #[case("{{salt}}")]
+    #[case("{salt {pepper}}")]
+    #[case("{salt}(1")]
+    #[case("{salt}(pinch)")]
+    #[case("{salt}(1 / )")]
+    #[case("{salt}(1..2)")]
+    #[case("/* stir")]
+    fn test_grammar_errors(#[case] input: &str) {
+        assert!(parse(input).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::parser::parse_spanned_partial;
+use crate::{ParseOptions, Token};
+
+/// What a highlighted region of the source is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum HighlightKind {
+    /// The curly braces and the name, like `{salt}`
+    IngredientName,
+    /// The parenthesis and the amounts, like `(1 pinch)`
+    IngredientAmount,
+    /// The reference and its braces, like `@{tomato sauce}`
+    RecipeRef,
+    Material,
+    Timer,
+    MetadataKey,
+    MetadataValue,
+    Comment,
+    Section,
+    /// A markdown link or image
+    Link,
+    Backstory,
+}
+
+/// A region of the source that should be highlighted
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Highlight {
+    /// Byte range in the source
+    pub range: Range<usize>,
+    pub kind: HighlightKind,
+}
+
+/// Regions of the source to highlight in an editor, sorted and without
+/// overlaps.
+///
+/// Plain words and spaces are not highlighted. While a recipe is being
+/// typed it's often invalid, so the regions found before the first error
+/// are still returned.
+///
+/// ```
+/// use recipe_parser::{highlight, HighlightKind};
+///
+/// let highlights = highlight("Add {salt}(1 pinch)");
+/// assert_eq!(highlights[0].range, 4..10);
+/// assert_eq!(highlights[0].kind, HighlightKind::IngredientName);
+/// assert_eq!(highlights[1].range, 10..19);
+/// assert_eq!(highlights[1].kind, HighlightKind::IngredientAmount);
+/// ```
+pub fn highlight(input: &str) -> Vec<Highlight> {
+    let mut highlights = vec![];
+    for (token, span) in parse_spanned_partial(input, &ParseOptions::default()) {
+        let mut push = |range: Range<usize>, kind| {
+            if !range.is_empty() {
+                highlights.push(Highlight { range, kind });
+            }
+        };
+        let is_ref = matches!(token, Token::RecipeRef { .. });
+        match token {
+            Token::Ingredient {
+                name: Cow::Borrowed(name),
+                amounts,
+            }
+            | Token::RecipeRef { name, amounts } => {
+                let kind = if is_ref {
+                    HighlightKind::RecipeRef
+                } else {
+                    HighlightKind::IngredientName
+                };
+                let name_end = offset_in(input, name) + name.len();
+                let braces_end = input[name_end..span.end]
+                    .find('}')
+                    .map_or(span.end, |close| name_end + close + 1);
+                push(span.start..braces_end, kind);
+                if !amounts.is_empty() || braces_end < span.end {
+                    push(braces_end..span.end, HighlightKind::IngredientAmount);
+                }
+            }
+            // Only `Recipe::rename_ingredient` gives a name its own storage
+            Token::Ingredient { .. } => push(span, HighlightKind::IngredientName),
+            Token::Metadata { key, value } => {
+                let key_start = offset_in(input, key);
+                push(key_start..key_start + key.len(), HighlightKind::MetadataKey);
+                let value_start = offset_in(input, value);
+                push(
+                    value_start..value_start + value.len(),
+                    HighlightKind::MetadataValue,
+                );
+            }
+            Token::Material(_) => push(span, HighlightKind::Material),
+            Token::Timer { .. } => push(span, HighlightKind::Timer),
+            Token::Comment(_) => {
+                // `/* */` comments consume the spaces after them
+                let end = span.start + input[span.clone()].trim_end().len();
+                push(span.start..end, HighlightKind::Comment)
+            }
+            Token::Section(_) => push(span, HighlightKind::Section),
+            Token::Link { .. } | Token::Image { .. } => push(span, HighlightKind::Link),
+            Token::Backstory(_) => push(span, HighlightKind::Backstory),
+            Token::Word(_) | Token::Space(_) => {}
+        }
+    }
+    highlights
+}
+
+/// Byte offset of a slice that borrows from the input
+fn offset_in(input: &str, part: &str) -> usize {
+    part.as_ptr() as usize - input.as_ptr() as usize
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sources<'a>(input: &'a str, highlights: &[Highlight]) -> Vec<(HighlightKind, &'a str)> {
+        highlights
+            .iter()
+            .map(|h| (h.kind, &input[h.range.clone()]))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_every_kind() {
+        let input = ">> name: soup\n= Broth\nBoil {water}(1 l) in a &{pot} for t{5 min} /* hot */ with @{stock}(1 cup)\n---\nstory";
+        let highlights = highlight(input);
+        assert_eq!(
+            sources(input, &highlights),
+            vec![
+                (HighlightKind::MetadataKey, "name"),
+                (HighlightKind::MetadataValue, "soup"),
+                (HighlightKind::Section, "= Broth"),
+                (HighlightKind::IngredientName, "{water}"),
+                (HighlightKind::IngredientAmount, "(1 l)"),
+                (HighlightKind::Material, "&{pot}"),
+                (HighlightKind::Timer, "t{5 min}"),
+                (HighlightKind::Comment, "/* hot */"),
+                (HighlightKind::RecipeRef, "@{stock}"),
+                (HighlightKind::IngredientAmount, "(1 cup)"),
+                (HighlightKind::Backstory, "\n---\nstory"),
+            ]
+        );
+        assert!(
+            highlights
+                .windows(2)
+                .all(|pair| pair[0].range.end <= pair[1].range.start)
+        );
+    }
+
+    #[test]
+    fn test_highlight_empty_amount_and_spaces_in_name() {
+        let input = "{ salt }() {pepper}";
+        assert_eq!(
+            sources(input, &highlight(input)),
+            vec![
+                (HighlightKind::IngredientName, "{ salt }"),
+                (HighlightKind::IngredientAmount, "()"),
+                (HighlightKind::IngredientName, "{pepper}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_invalid_recipe_till_the_error() {
+        let input = "Boil {water} and {salt";
+        assert_eq!(
+            sources(input, &highlight(input)),
+            vec![(HighlightKind::IngredientName, "{water}")]
+        );
+    }
+}
@@ -1,4 +1,76 @@
+//! Parser of recipe-lang.
+//!
+//! The `std` feature is enabled by default. Without it the crate is `no_std`
+//! and only needs `alloc`, keeping the parser ([`parse`], [`parse_with_options`],
+//! [`parse_spanned`], [`parse_until`], [`parse_each`]), the borrowed [`Token`]
+//! with its `Display`, [`Quantity`], [`parse_duration`], [`highlight`], [`explain`],
+//! [`lint`], [`metadata_pairs`], [`render_frontmatter`], [`strip_backstory`],
+//! [`text_spans`], [`RecipeError`] and [`RecipeBuilder`].
+//!
+//! The structured [`Recipe`] and everything built on it, like aggregation,
+//! diffs, rendering and units, require `std`.
+//!
+//! The syntax accepted by the parser is described in [`grammar`].
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod aggregate;
+#[cfg(feature = "binary")]
+mod binary;
+mod builder;
+#[cfg(feature = "std")]
+mod diff;
+mod duration;
+mod error;
+mod explain;
+pub mod grammar;
+mod highlight;
+mod lint;
+mod metadata;
 mod parser;
+mod quantity;
+#[cfg(feature = "std")]
+mod recipe;
+#[cfg(feature = "std")]
+mod render;
+mod transform;
+#[cfg(feature = "std")]
+mod unit;
 
-pub use parser::parse;
-pub use parser::Token;
+#[cfg(feature = "std")]
+pub use aggregate::{
+    AggregatedIngredient, IngredientNormalizer, IngredientSort, Total, aggregate,
+    ingredient_frequencies, resolve_refs, sort_ingredients,
+};
+#[cfg(feature = "binary")]
+pub use binary::{DecodeError, from_bytes, to_bytes};
+pub use builder::RecipeBuilder;
+#[cfg(feature = "std")]
+pub use diff::{IngredientChange, MetadataChange, RecipeDiff, diff};
+pub use duration::parse_duration;
+pub use error::RecipeError;
+pub use explain::explain;
+pub use highlight::{Highlight, HighlightKind, highlight};
+#[cfg(feature = "std")]
+pub use lint::validate_metadata;
+pub use lint::{Lint, lint};
+pub use metadata::{metadata_pairs, render_frontmatter};
+pub use parser::{Amount, Percentage, TimerKind, Token, display_amounts};
+pub use parser::{Error, ParseOptions, parse, parse_with_options};
+pub use parser::{
+    parse_each, parse_each_with_options, parse_spanned, parse_spanned_with_options, parse_until,
+    parse_until_with_options,
+};
+pub use quantity::{DecimalMark, Quantity};
+#[cfg(feature = "std")]
+pub use recipe::{
+    FlowGraph, FlowNode, Ingredient, Material, Nutrition, NutritionFact, Recipe, RecipeOptions,
+    RecipeRef, RecipeSummary, ResolvedAmount, Servings, Timer,
+};
+#[cfg(feature = "std")]
+pub use render::{IngredientList, RenderOptions, render_text, to_csv};
+pub use transform::{TextSpan, strip_backstory, strip_comments, text_spans};
+#[cfg(feature = "std")]
+pub use unit::{AmountKind, Unit, UnitRegistry};
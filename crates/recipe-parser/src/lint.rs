@@ -0,0 +1,240 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::parser::{is_valid_char, parse_spanned_partial};
+use crate::{ParseOptions, Token};
+
+/// A likely mistake in a recipe, it doesn't stop the recipe from being parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Lint {
+    pub message: String,
+    /// Byte range in the source, when the problem can be located
+    pub range: Option<Range<usize>>,
+}
+
+/// Look for parts of the source that are probably not parsed the way the
+/// author expects, like a `---` that doesn't start the backstory.
+///
+/// ```
+/// use recipe_parser::lint;
+///
+/// let lints = lint("Boil the water --- then serve");
+/// assert_eq!(lints[0].range, Some(15..18));
+/// ```
+pub fn lint(input: &str) -> Vec<Lint> {
+    let mut lints = vec![];
+    for (token, span) in parse_spanned_partial(input, &ParseOptions::default()) {
+        match token {
+            Token::Word(word) if word.len() >= 3 && word.chars().all(|c| c == '-') => {
+                lints.push(Lint {
+                    message: format!(
+                        "`{word}` is taken as text, the backstory starts with `---` alone in its line"
+                    ),
+                    range: Some(span),
+                });
+            }
+            Token::Word(word) => {
+                if let Some(offset) = glued_name(word) {
+                    lints.push(Lint {
+                        message: format!(
+                            "`{word}` is taken as text, add a space before `{}` to use it",
+                            &word[offset..]
+                        ),
+                        range: Some(span.start + offset..span.end),
+                    });
+                }
+            }
+            Token::Backstory(backstory) => {
+                let start = span.end - backstory.len();
+                let mut line_start = start;
+                for line in backstory.split_inclusive('\n') {
+                    if line.trim() == "---" {
+                        let offset = line_start + line.find('-').unwrap_or(0);
+                        lints.push(Lint {
+                            message:
+                                "only the first `---` starts the backstory, this one is part of it"
+                                    .to_string(),
+                            range: Some(offset..offset + 3),
+                        });
+                    }
+                    line_start += line.len();
+                }
+            }
+            _ => {}
+        }
+    }
+    lints
+}
+
+/// Check the metadata keys of a recipe against the ones a collection uses.
+///
+/// Unknown keys are reported with the closest known key, when it's only a
+/// typo away, and so are keys without a value. Keys are compared ignoring
+/// case. The metadata of a [`Recipe`](crate::Recipe) has no location, so
+/// the lints have no range.
+///
+/// ```
+/// use recipe_parser::{Recipe, validate_metadata};
+///
+/// let recipe = Recipe::try_from(">> serving: 4\nBoil").unwrap();
+/// let lints = validate_metadata(&recipe, &["name", "servings", "tags"]);
+/// assert_eq!(
+///     lints[0].message,
+///     "unknown metadata key `serving`, did you mean `servings`?"
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn validate_metadata(recipe: &crate::Recipe, known_keys: &[&str]) -> Vec<Lint> {
+    let mut lints = vec![];
+    for token in &recipe.instructions {
+        let Token::Metadata { key, value } = token else {
+            continue;
+        };
+        let key = key.trim();
+        let lowercase = key.to_lowercase();
+        if !known_keys
+            .iter()
+            .any(|known| known.to_lowercase() == lowercase)
+        {
+            let closest = known_keys
+                .iter()
+                .map(|known| (levenshtein(&lowercase, &known.to_lowercase()), known))
+                .filter(|(distance, _)| *distance <= MAX_TYPO_DISTANCE)
+                .min_by_key(|(distance, _)| *distance);
+            let message = match closest {
+                Some((_, known)) => {
+                    format!("unknown metadata key `{key}`, did you mean `{known}`?")
+                }
+                None => format!("unknown metadata key `{key}`"),
+            };
+            lints.push(Lint {
+                message,
+                range: None,
+            });
+        }
+        if value.trim().is_empty() {
+            lints.push(Lint {
+                message: format!("metadata `{key}` has no value"),
+                range: None,
+            });
+        }
+    }
+    lints
+}
+
+/// Edits a key can be away from a known one to be suggested
+#[cfg(feature = "std")]
+const MAX_TYPO_DISTANCE: usize = 2;
+
+/// Number of characters to insert, remove or replace to turn `a` into `b`
+#[cfg(feature = "std")]
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a != *b);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Offset of a name in curly braces at the end of a word, like
+/// `warm{butter}` or `heat&{pot}`. A `t` is kept in the word, since
+/// `heat{butter}` is more likely an ingredient than a timer.
+fn glued_name(word: &str) -> Option<usize> {
+    let open = word.find('{').filter(|open| *open > 0)?;
+    let name = word[open + 1..].strip_suffix('}')?;
+    if name.trim().is_empty() || !name.chars().all(is_valid_char) {
+        return None;
+    }
+    let prefix = match &word[..open] {
+        before if before.ends_with(['&', '@']) && open > 1 => open - 1,
+        _ => open,
+    };
+    Some(prefix)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("Melt the warm{butter}", vec![("{butter}", 13..21)])]
+    #[case("heat&{pot} and wait t{5 min}", vec![("&{pot}", 4..10)])]
+    #[case("at{5 min}", vec![])]
+    #[case("heat{butter}", vec![("{butter}", 4..12)])]
+    #[case("add the rest{}", vec![])]
+    #[case("{salt}", vec![])]
+    #[case("a{b", vec![])]
+    fn test_lint_glued_name(#[case] input: &str, #[case] expected: Vec<(&str, Range<usize>)>) {
+        let lints: Vec<(&str, Range<usize>)> = lint(input)
+            .into_iter()
+            .filter_map(|lint| lint.range)
+            .map(|range| (&input[range.clone()], range))
+            .collect();
+        assert_eq!(lints, expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    #[case("servings", "servings", 0)]
+    #[case("serving", "servings", 1)]
+    #[case("tag", "tags", 1)]
+    #[case("nmae", "name", 2)]
+    #[case("", "abc", 3)]
+    #[case("kitten", "sitting", 3)]
+    #[case("crème", "creme", 1)]
+    fn test_levenshtein(#[case] a: &str, #[case] b: &str, #[case] expected: usize) {
+        assert_eq!(levenshtein(a, b), expected);
+        assert_eq!(levenshtein(b, a), expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_validate_metadata() {
+        let recipe = crate::Recipe::try_from(
+            ">> Name: soup\n>> tag: vegan\n>> servings:\n>> difficulty: easy\n>> source: \nBoil",
+        )
+        .unwrap();
+        let messages: Vec<String> = validate_metadata(&recipe, &["name", "servings", "tags"])
+            .into_iter()
+            .map(|lint| lint.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "unknown metadata key `tag`, did you mean `tags`?",
+                "metadata `servings` has no value",
+                "unknown metadata key `difficulty`",
+                "unknown metadata key `source`",
+                "metadata `source` has no value",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("Boil.\n---\nA story", vec![])]
+    #[case("Boil.\n---  \nA story", vec![])]
+    #[case("---\nBoil.", vec![0..3])]
+    #[case("Boil ---", vec![5..8])]
+    #[case("Boil.\n--- A story", vec![6..9])]
+    #[case("Boil.\n----\nA story", vec![6..10])]
+    #[case("Boil.\n---\nA story\n  ---\nmore", vec![20..23])]
+    fn test_lint_backstory_delimiter(#[case] input: &str, #[case] expected: Vec<Range<usize>>) {
+        let ranges: Vec<Range<usize>> = lint(input)
+            .into_iter()
+            .filter_map(|lint| lint.range)
+            .collect();
+        assert_eq!(ranges, expected);
+    }
+}
@@ -0,0 +1,198 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::Token;
+
+/// The `>> key: value` metadata in the order it was written, with duplicates
+///
+/// ```
+/// use recipe_parser::{metadata_pairs, parse};
+///
+/// let tokens = parse(">> tags: vegan\n>> name: soup\n>> tags: easy\nBoil").unwrap();
+/// assert_eq!(
+///     metadata_pairs(&tokens),
+///     vec![("tags", "vegan"), ("name", "soup"), ("tags", "easy")]
+/// );
+/// ```
+pub fn metadata_pairs<'a>(tokens: &[Token<'a>]) -> Vec<(&'a str, &'a str)> {
+    tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Metadata { key, value } => Some((*key, *value)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Write the metadata as a YAML front matter block, in source order.
+///
+/// Values are quoted when YAML would read them differently, like `4`, `yes`
+/// or `2024-01-01` that are not read as strings, and multiline
+/// values are written as literal blocks. Duplicated keys are kept, which
+/// strict YAML parsers reject.
+///
+/// ```
+/// use recipe_parser::{parse, render_frontmatter};
+///
+/// let tokens = parse(">> name: soup\n>> source: https://example.com\nBoil").unwrap();
+/// assert_eq!(
+///     render_frontmatter(&tokens),
+///     "---\nname: soup\nsource: https://example.com\n---\n"
+/// );
+/// ```
+pub fn render_frontmatter(tokens: &[Token]) -> String {
+    let mut out = String::from("---\n");
+    for (key, value) in metadata_pairs(tokens) {
+        let key = yaml_scalar(key.trim());
+        if value.contains('\n') {
+            writeln!(out, "{key}: |").expect("writing to a String cannot fail");
+            for line in value.lines() {
+                if line.trim().is_empty() {
+                    out.push('\n');
+                } else {
+                    writeln!(out, "  {line}").expect("writing to a String cannot fail");
+                }
+            }
+        } else {
+            writeln!(out, "{key}: {}", yaml_scalar(value))
+                .expect("writing to a String cannot fail");
+        }
+    }
+    out.push_str("---\n");
+    out
+}
+
+/// Plain YAML scalar when it's read back as the same string, or else double quoted
+fn yaml_scalar(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.trim() != value
+        || value.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.ends_with(':')
+        || value.contains(['\n', '\r', '\t'])
+        || is_yaml_non_string(value);
+    if !needs_quotes {
+        return value.into();
+    }
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Whether a YAML 1.1 or 1.2 resolver reads the plain value as a number,
+/// bool, null or timestamp. A few strings are matched too, like `1.2.3`,
+/// quoting them is harmless.
+fn is_yaml_non_string(value: &str) -> bool {
+    let value = value.to_lowercase();
+    let reserved = [
+        "true", "false", "yes", "no", "y", "n", "on", "off", "null", "~", ".nan",
+    ];
+    let unsigned = value.strip_prefix(['+', '-']).unwrap_or(&value);
+    if reserved.contains(&value.as_str()) || unsigned == ".inf" {
+        return true;
+    }
+    // Ints, floats and sexagesimals like `1:30`, in any base
+    let digits = unsigned.strip_prefix('.').unwrap_or(unsigned);
+    if digits.starts_with(|c: char| c.is_ascii_digit())
+        && unsigned
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() || "_.:+-xo".contains(c))
+    {
+        return true;
+    }
+    // Timestamps with a time, like `2024-01-01 10:30:00`
+    let date = unsigned.split([' ', '\t', 't']).next().unwrap_or_default();
+    let parts: Vec<&str> = date.split('-').collect();
+    parts.len() == 3
+        && parts[0].len() == 4
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use rstest::*;
+
+    #[test]
+    fn test_metadata_pairs_without_metadata() {
+        let tokens = parse("Boil the {water}").unwrap();
+        assert!(metadata_pairs(&tokens).is_empty());
+    }
+
+    #[rstest]
+    #[case("soup", "soup")]
+    #[case("4", "\"4\"")]
+    #[case("4 people", "4 people")]
+    #[case("1.5", "\"1.5\"")]
+    #[case(".5", "\".5\"")]
+    #[case("+12", "\"+12\"")]
+    #[case("1_000", "\"1_000\"")]
+    #[case("1e3", "\"1e3\"")]
+    #[case("0x1F", "\"0x1F\"")]
+    #[case("0o17", "\"0o17\"")]
+    #[case("1:30", "\"1:30\"")]
+    #[case("-.inf", "\"-.inf\"")]
+    #[case(".NaN", "\".NaN\"")]
+    #[case("2024-01-01", "\"2024-01-01\"")]
+    #[case("2024-01-01 10:30:00", "\"2024-01-01 10:30:00\"")]
+    #[case("2024-01-01T10:30:00Z", "\"2024-01-01T10:30:00Z\"")]
+    #[case("Y", "\"Y\"")]
+    #[case("Off", "\"Off\"")]
+    #[case("~", "\"~\"")]
+    #[case("NULL", "\"NULL\"")]
+    #[case("v1.5", "v1.5")]
+    #[case("20 min", "20 min")]
+    #[case("https://example.com", "https://example.com")]
+    #[case("", "\"\"")]
+    #[case("yes", "\"yes\"")]
+    #[case("a: b", "\"a: b\"")]
+    #[case("#vegan", "\"#vegan\"")]
+    #[case("soup # hot", "\"soup # hot\"")]
+    #[case("say \"hi\"", "say \"hi\"")]
+    #[case("'quoted'", "\"'quoted'\"")]
+    #[case("- item", "\"- item\"")]
+    #[case("a\\b\tc", "\"a\\\\b\\tc\"")]
+    fn test_yaml_scalar(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(yaml_scalar(value), expected);
+    }
+
+    #[test]
+    fn test_render_frontmatter() {
+        let tokens = parse(
+            ">> name: soup\n>> tags: #vegan\n>>> description: a hot soup\n\nfor winter\n>> servings: 4",
+        )
+        .unwrap();
+        assert_eq!(
+            render_frontmatter(&tokens),
+            "---\nname: soup\ntags: \"#vegan\"\ndescription: |\n  a hot soup\n\n  for winter\nservings: \"4\"\n---\n"
+        );
+        assert_eq!(render_frontmatter(&[]), "---\n---\n");
+    }
+
+    #[test]
+    fn test_render_frontmatter_keeps_values_strings() {
+        let tokens = parse(
+            ">> servings: 4\n>> ratio: 1.5\n>> date: 2024-01-01\n>> vegan: yes\n>> notes: ~\nBoil",
+        )
+        .unwrap();
+        assert_eq!(
+            render_frontmatter(&tokens),
+            "---\nservings: \"4\"\nratio: \"1.5\"\ndate: \"2024-01-01\"\nvegan: \"yes\"\nnotes: \"~\"\n---\n"
+        );
+    }
+}
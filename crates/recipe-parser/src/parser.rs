@@ -1,32 +1,108 @@
-use std::fmt::Display;
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
+use core::ops::ControlFlow;
+use core::ops::Range;
 
-use winnow::ascii::{digit1, line_ending, multispace0, multispace1, space0, space1};
-use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, separated};
+use winnow::ascii::{digit1, line_ending, multispace0, space0};
+use winnow::combinator::{
+    alt, cut_err, delimited, eof, fail, not, opt, preceded, repeat, separated, terminated,
+};
 use winnow::error::{ContextError, ParseError, StrContext, StrContextValue};
-use winnow::token::{one_of, rest, take_till, take_until, take_while};
-use winnow::{LocatingSlice, ModalResult, Parser};
+use winnow::stream::{Location, Stream};
+use winnow::token::{one_of, rest, take, take_till, take_until, take_while};
+use winnow::{LocatingSlice, ModalResult, Parser, Stateful};
 
-type Input<'a> = LocatingSlice<&'a str>;
+#[cfg(feature = "std")]
+use crate::{AmountKind, Unit, UnitRegistry};
+use crate::{DecimalMark, Quantity};
+
+type Input<'a, 'o> = Stateful<LocatingSlice<&'a str>, &'o ParseOptions>;
+
+/// Options to customize what the parser accepts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Parse `// comment` till the end of the line, enabled by default
+    pub line_comments: bool,
+    /// A backslash takes the next symbol literally, like `\{`, enabled by default
+    pub escapes: bool,
+    /// Extra symbols allowed inside curly braces, on top of the default ones
+    pub extra_symbols: Cow<'static, str>,
+    /// Text before the curly braces of a material, `&` by default.
+    /// When empty, materials are not parsed.
+    pub material_prefix: Cow<'static, str>,
+    /// Text before the curly braces of a timer, `t` by default.
+    /// When empty, timers are not parsed.
+    pub timer_prefix: Cow<'static, str>,
+    /// Quantities can be sums and products, like `{eggs}(2*3)` or
+    /// `{milk}(1+1/2 cups)`, disabled by default
+    pub arithmetic: bool,
+}
+
+impl ParseOptions {
+    pub const DEFAULT: Self = Self {
+        line_comments: true,
+        escapes: true,
+        extra_symbols: Cow::Borrowed(""),
+        material_prefix: Cow::Borrowed("&"),
+        timer_prefix: Cow::Borrowed("t"),
+        arithmetic: false,
+    };
+
+    /// True when the text starts with the prefix of a material or a timer
+    /// followed by a curly brace
+    fn starts_with_prefix(&self, text: &str) -> bool {
+        [&self.material_prefix, &self.timer_prefix]
+            .into_iter()
+            .any(|prefix| {
+                !prefix.is_empty()
+                    && text
+                        .strip_prefix(prefix.as_ref())
+                        .is_some_and(|rest| rest.starts_with('{'))
+            })
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static DEFAULT_OPTIONS: ParseOptions = ParseOptions::DEFAULT;
+
+/// Error returned when the recipe cannot be parsed, it points to
+/// the offset where the parser failed
+pub type Error<'a, 'o> =
+    ParseError<Stateful<LocatingSlice<&'a str>, &'o ParseOptions>, ContextError>;
 
 /// Parses a valid string from the input.
 ///
 /// This function takes a mutable reference to a string slice and parses a valid string from it.
 /// A valid string can contain alphanumeric characters as well as certain symbols and spaces.
 /// The function returns a `ModalResult` containing the parsed valid string.
-fn parse_valid_string<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
-    let spaces_and_symbols = "\t /-_@.,%#'";
-    take_while(1.., move |c: char| {
-        c.is_alphanumeric() || spaces_and_symbols.contains(c)
-    })
-    .parse_next(input)
+fn parse_valid_string<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    let extra_symbols = &input.state.extra_symbols;
+    take_while(1.., |c: char| is_valid_char(c) || extra_symbols.contains(c)).parse_next(input)
+}
+
+/// Characters allowed inside curly braces and units
+pub(crate) fn is_valid_char(c: char) -> bool {
+    let spaces_and_symbols = "\t /-_@.,%#'&+!:";
+    c.is_alphanumeric() || spaces_and_symbols.contains(c)
 }
 
+/// Characters that can be escaped with a backslash
+pub(crate) const ESCAPABLE_CHARS: [char; 8] = ['\\', '{', '}', '>', '/', '-', '[', '='];
+
 /// Parse comments in the form of:
 ///
 /// ```recp
 /// /* */
 /// ```
-fn parse_comment<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
+fn parse_comment<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
     delimited(
         "/*",
         cut_err(take_until(0.., "*/"))
@@ -37,21 +113,55 @@ fn parse_comment<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
     .parse_next(input)
 }
 
+/// Parse line comments, which run till the end of the line:
+///
+/// ```recp
+/// // taste and adjust
+/// ```
+///
+/// The line ending is not consumed, so the spacing is preserved.
+fn parse_line_comment<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    if !input.state.line_comments {
+        return fail.parse_next(input);
+    }
+    preceded("//", take_till(0.., ('\r', '\n')))
+        .map(|v: &str| v.trim())
+        .parse_next(input)
+}
+
 /// Parse curly braces delimited utf-8
 ///
 /// ```recp
 /// {salt}
 /// {tomatoes}
 /// ```
-fn parse_curly<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
+///
+/// Curly braces cannot be nested, `{{salt}}` and `{salt {pepper}}` are rejected
+/// pointing at the inner `{`.
+///
+/// Names cannot span multiple lines, `{sweet\npotato}` is rejected. Tabs are
+/// kept as they are.
+fn parse_curly<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
     delimited(
-        "{",
+        (
+            "{",
+            cut_err(not("{")).context(StrContext::Expected(StrContextValue::Description(
+                "a name, curly braces cannot be nested",
+            ))),
+        ),
         parse_valid_string.map(|v| v.trim()),
-        cut_err("}").context(StrContext::Expected(StrContextValue::CharLiteral('}'))),
+        (
+            cut_err(not(one_of(['\r', '\n']))).context(StrContext::Expected(
+                StrContextValue::Description(SAME_LINE_CURLY),
+            )),
+            cut_err("}").context(StrContext::Expected(StrContextValue::CharLiteral('}'))),
+        ),
     )
     .parse_next(input)
 }
 
+pub(crate) const SAME_LINE_CURLY: &str = "a `}` in the same line, names cannot span multiple lines";
+
 /// The amount of an ingredient must be numeric
 /// with a few symbols allowed.
 ///
@@ -62,35 +172,88 @@ fn parse_curly<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
 /// 3_000_000
 /// 2/3
 /// ```
-fn parse_quantity<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
-    let quantity = separated(1.., digit1, one_of(['.', ',', '/', '_']))
-        .map(|()| ())
-        .take();
-
-    cut_err(quantity)
+///
+/// With [`ParseOptions::arithmetic`], sums and products of them too, as
+/// long as the result is not negative. Otherwise only the first number
+/// is the quantity.
+///
+/// ```recp
+/// 2*3
+/// 1 + 1/2
+/// ```
+fn parse_quantity<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    if input.state.arithmetic
+        && let Some(expression) = opt(parse_quantity_expression).parse_next(input)?
+    {
+        return Ok(expression);
+    }
+    cut_err(parse_number)
         .context(StrContext::Expected(StrContextValue::Description(
             "a quantity value, like 3, 1.2, 1/2 or 1_000",
         )))
         .parse_next(input)
 }
 
+fn parse_number<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    let separators = ['.', ',', '/', '_'];
+    terminated(
+        (
+            opt(digit1),
+            repeat(0.., (one_of(separators), digit1)).map(|()| ()),
+        )
+            .take()
+            .verify(|v: &str| !v.is_empty()),
+        not(one_of(separators)),
+    )
+    .parse_next(input)
+}
+
+fn parse_quantity_expression<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    (
+        parse_number,
+        repeat(1.., (space0, one_of(['+', '-', '*']), space0, parse_number)).map(|()| ()),
+    )
+        .take()
+        .verify(|v: &str| Quantity::parse(v).is_some_and(|quantity| quantity.numerator() >= 0))
+        .parse_next(input)
+}
+
 /// Parse units like kg, kilograms, pinch, etc.
-fn parse_unit<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
-    parse_valid_string.parse_next(input)
+///
+/// A `/` is not part of the unit, it separates alternative amounts.
+fn parse_unit<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    take_while(1.., |c: char| is_valid_char(c) && c != '/').parse_next(input)
 }
 
-/// Ingredient amounts are surrounded by parenthesis
-fn parse_ingredient_amount<'a>(
-    input: &mut Input<'a>,
-) -> ModalResult<(Option<&'a str>, Option<&'a str>)> {
-    delimited(
-        ("(", space0),
-        (
-            opt(parse_quantity),
-            opt(preceded(space0, parse_unit.map(|v| v.trim()))),
-        ),
-        cut_err(")").context(StrContext::Expected(StrContextValue::CharLiteral(')'))),
+/// An amount is composed by an optional quantity and unit
+fn parse_amount<'a>(input: &mut Input<'a, '_>) -> ModalResult<Amount<'a>> {
+    (
+        opt(parse_quantity),
+        opt(preceded(space0, parse_unit.map(|v| v.trim()))),
     )
+        .map(|(quantity, unit)| Amount::new(quantity, unit))
+        .parse_next(input)
+}
+
+/// Ingredient amounts are surrounded by parenthesis, alternative
+/// amounts in other units are separated by `/`.
+///
+/// Empty parenthesis are a placeholder, they have no amounts.
+///
+/// ```recp
+/// (200gr)
+/// (113gr / 1 stick)
+/// ()
+/// ```
+fn parse_ingredient_amount<'a>(input: &mut Input<'a, '_>) -> ModalResult<Vec<Amount<'a>>> {
+    alt((
+        ("(", space0, ")").map(|_| vec![]),
+        delimited(
+            ("(", space0),
+            separated(1.., parse_amount, (space0, "/", space0)),
+            cut_err(")").context(StrContext::Expected(StrContextValue::CharLiteral(')'))),
+        ),
+    ))
     .parse_next(input)
 }
 
@@ -100,11 +263,14 @@ fn parse_ingredient_amount<'a>(
 /// {quinoa}(200gr)
 /// {tomatoes}(2)
 /// {sweet potatoes}(2)
+/// {butter}(113gr / 1 stick)
 /// ```
-fn parse_ingredient<'a>(
-    input: &mut Input<'a>,
-) -> ModalResult<(&'a str, Option<(Option<&'a str>, Option<&'a str>)>)> {
-    (parse_curly, opt(parse_ingredient_amount)).parse_next(input)
+fn parse_ingredient<'a>(input: &mut Input<'a, '_>) -> ModalResult<(&'a str, Vec<Amount<'a>>)> {
+    (
+        parse_curly,
+        opt(parse_ingredient_amount).map(Option::unwrap_or_default),
+    )
+        .parse_next(input)
 }
 
 /// Materials format:
@@ -114,44 +280,196 @@ fn parse_ingredient<'a>(
 /// &{small jar}
 /// &{stick}
 /// ```
-fn parse_material<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
-    preceded("&", parse_curly).parse_next(input)
+fn parse_material<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    let prefix = input.state.material_prefix.as_ref();
+    if prefix.is_empty() {
+        return fail.parse_next(input);
+    }
+    preceded(prefix, parse_curly).parse_next(input)
 }
 
-/// Materials format:
+/// Timers format, optionally followed by their kind:
 ///
 /// ```recp
 /// t{25 minutes}
 /// t{10 sec}
+/// t{5 minutes}!active
+/// t{30 minutes}!passive
 /// ```
-fn parse_timer<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
-    preceded("t", parse_curly).parse_next(input)
+fn parse_timer<'a>(input: &mut Input<'a, '_>) -> ModalResult<(&'a str, TimerKind)> {
+    let prefix = input.state.timer_prefix.as_ref();
+    if prefix.is_empty() {
+        return fail.parse_next(input);
+    }
+    let kind = terminated(
+        preceded(
+            "!",
+            alt((
+                "active".value(TimerKind::Active),
+                "passive".value(TimerKind::Passive),
+            )),
+        ),
+        not(take_while(1, |c: char| c.is_alphanumeric())),
+    );
+    (
+        preceded(prefix, parse_curly),
+        opt(kind).map(Option::unwrap_or_default),
+    )
+        .parse_next(input)
 }
 
-/// Parse a reference to another recipe
+/// Parse a reference to another recipe, `@recipe{}` is a longer spelling
+/// of `@{}`
 ///
 /// ```recp
 /// @{woile/special-tomato-sauce}
 /// @{woile/special-tomato-sauce}(100 ml)
+/// @recipe{pizza dough}
 /// ```
-fn parse_recipe_ref<'a>(
-    input: &mut Input<'a>,
-) -> ModalResult<(&'a str, Option<(Option<&'a str>, Option<&'a str>)>)> {
-    preceded("@", (parse_curly, opt(parse_ingredient_amount))).parse_next(input)
+fn parse_recipe_ref<'a>(input: &mut Input<'a, '_>) -> ModalResult<(&'a str, Vec<Amount<'a>>)> {
+    preceded(("@", opt("recipe")), parse_ingredient).parse_next(input)
 }
 
 /// Tokens are separated into words
-fn parse_word<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
-    take_till(1.., (' ', '\t', '\r', '\n')).parse_next(input)
+fn parse_word<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    take_till(1.., (' ', '\t', '\r', '\n', '\\')).parse_next(input)
 }
 
-fn parse_metadata<'a>(input: &mut Input<'a>) -> ModalResult<(&'a str, &'a str)> {
-    preceded(
+/// Markdown links in the steps
+///
+/// ```recp
+/// fold it like [this](https://example.com/fold)
+/// ```
+fn parse_link<'a>(input: &mut Input<'a, '_>) -> ModalResult<(&'a str, &'a str)> {
+    (
+        delimited("[", take_till(1.., (']', '\r', '\n')), "]"),
+        delimited("(", take_till(1.., (')', ' ', '\t', '\r', '\n')), ")"),
+    )
+        .parse_next(input)
+}
+
+/// Markdown images in the steps
+///
+/// ```recp
+/// ![folded dumplings](dumplings.png)
+/// ```
+fn parse_image<'a>(input: &mut Input<'a, '_>) -> ModalResult<(&'a str, &'a str)> {
+    preceded("!", parse_link).parse_next(input)
+}
+
+/// A backslash takes the next symbol literally
+///
+/// ```recp
+/// \{not an ingredient}
+/// \// not a comment
+/// ```
+fn parse_escaped<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    if !input.state.escapes {
+        return fail.parse_next(input);
+    }
+    preceded("\\", one_of(ESCAPABLE_CHARS).take()).parse_next(input)
+}
+
+/// Check if the whole string is a valid quantity
+pub(crate) fn is_valid_quantity(value: &str) -> bool {
+    let input = Stateful {
+        input: LocatingSlice::new(value),
+        state: &DEFAULT_OPTIONS,
+    };
+    parse_quantity.parse(input).is_ok()
+}
+
+/// Metadata goes in its own line, the line ending is part of it.
+/// A `>>` after other text in the line is a word.
+///
+/// ```recp
+/// >> tags: vegan
+/// ```
+fn parse_metadata<'a>(input: &mut Input<'a, '_>) -> ModalResult<(&'a str, &'a str)> {
+    if !is_line_start(input) {
+        return fail.parse_next(input);
+    }
+    delimited(
         (">>", space0),
         (
             take_while(1.., |c| c != ':'),
-            preceded((":", space0), take_until(0.., "\n")),
+            preceded((":", space0), take_till(0.., ('\r', '\n'))),
         ),
+        opt(line_ending),
+    )
+    .parse_next(input)
+}
+
+/// Multiline metadata uses `>>>`, the value continues till the next
+/// metadata, the backstory or the end of the input
+///
+/// ```recp
+/// >>> description: a long description
+/// that spans multiple lines
+/// >> tags: vegan
+/// ```
+fn parse_multiline_metadata<'a>(input: &mut Input<'a, '_>) -> ModalResult<(&'a str, &'a str)> {
+    if !is_line_start(input) {
+        return fail.parse_next(input);
+    }
+    preceded(
+        (">>>", space0),
+        (
+            take_while(1.., |c| c != ':' && c != '\n'),
+            preceded((":", space0), parse_multiline_value),
+        ),
+    )
+    .parse_next(input)
+}
+
+fn parse_multiline_value<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    let remaining: &str = input;
+    let mut len = remaining.len();
+    let mut line_start = 0;
+    for line in remaining.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        // The line ending before the next metadata is consumed,
+        // but the backstory needs it
+        if line_start > 0 && trimmed.starts_with(">>") {
+            len = line_start;
+            break;
+        }
+        if line_start > 0 && trimmed.trim_end() == "---" {
+            len = line_start - 1;
+            break;
+        }
+        line_start += line.len();
+    }
+    take(len).map(|v: &str| v.trim()).parse_next(input)
+}
+
+/// True when only spaces were found since the last newline
+fn is_line_start(input: &Input<'_, '_>) -> bool {
+    let offset = input.current_token_start();
+    let mut start = input.input;
+    start.reset_to_start();
+    let consumed: &str = *start;
+    // Only the spaces before the token are looked at, so long lines stay linear
+    let before = consumed[..offset].trim_end_matches([' ', '\t']);
+    before.is_empty() || before.ends_with('\n')
+}
+
+/// A section heading starts with `=` at the beginning of a line,
+/// trailing `=` are ignored
+///
+/// ```recp
+/// = For the dough
+/// == For the filling ==
+/// ```
+fn parse_section<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    if !is_line_start(input) {
+        return fail.parse_next(input);
+    }
+    preceded(
+        take_while(1.., '='),
+        take_till(1.., ('\r', '\n'))
+            .map(|v: &str| v.trim().trim_end_matches('=').trim_end())
+            .verify(|v: &str| !v.is_empty()),
     )
     .parse_next(input)
 }
@@ -162,18 +480,47 @@ fn parse_metadata<'a>(input: &mut Input<'a>) -> ModalResult<(&'a str, &'a str)>
 /// ---
 /// This recipe was given by my grandma
 /// ```
-fn parse_backstory<'a>(input: &mut Input<'a>) -> ModalResult<&'a str> {
+///
+/// The `---` must be on its own line, spaces around it are allowed.
+fn parse_backstory<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
     preceded(
         delimited(
             preceded(line_ending, multispace0),
             "---",
-            preceded(line_ending, multispace0),
+            (space0, alt((line_ending, eof)), multispace0),
         ),
         rest,
     )
     .parse_next(input)
 }
 
+/// A whole run of spaces, tabs and line endings, so two spaces are never
+/// next to each other. It stops before the line ending of a backstory.
+fn parse_space<'a>(input: &mut Input<'a, '_>) -> ModalResult<&'a str> {
+    let remaining: &str = input;
+    let mut len = remaining.len() - remaining.trim_start_matches([' ', '\t', '\r', '\n']).len();
+    // The rest of the run is whitespace, so only the first line ending
+    // can start a backstory
+    if let Some(line_ending) = remaining[..len].find('\n') {
+        let line_ending = if remaining[..line_ending].ends_with('\r') {
+            line_ending - 1
+        } else {
+            line_ending
+        };
+        let start = input.checkpoint();
+        input.next_slice(line_ending);
+        let backstory = parse_backstory.parse_next(input).is_ok();
+        input.reset(&start);
+        if backstory {
+            len = line_ending;
+        }
+    }
+    if len == 0 {
+        return fail.parse_next(input);
+    }
+    take(len).parse_next(input)
+}
+
 /* ****************
 * The main parser
 **************** */
@@ -191,101 +538,264 @@ pub enum Token<'a> {
         value: &'a str,
     },
     Ingredient {
-        name: &'a str,
-        quantity: Option<&'a str>,
-        unit: Option<&'a str>,
+        // Borrowed from the input, owned only after `Recipe::rename_ingredient`
+        name: Cow<'a, str>,
+        // The first amount is the primary one, the rest are alternatives
+        amounts: Vec<Amount<'a>>,
     },
     // Reference to another recipe
     RecipeRef {
         name: &'a str,
-        quantity: Option<&'a str>,
-        unit: Option<&'a str>,
+        amounts: Vec<Amount<'a>>,
+    },
+    Timer {
+        duration: &'a str,
+        kind: TimerKind,
     },
-    Timer(&'a str),
     Material(&'a str),
     Word(&'a str),
     Space(&'a str),
     Comment(&'a str),
     Backstory(&'a str),
+    // Heading of a group of steps, like `= For the dough`
+    Section(&'a str),
+    // Markdown link, like `[this](https://example.com)`
+    Link {
+        text: &'a str,
+        url: &'a str,
+    },
+    // Markdown image, like `![dumplings](dumplings.png)`
+    Image {
+        alt: &'a str,
+        url: &'a str,
+    },
+}
+
+/// The amount of an ingredient, like `200 gr`
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct Amount<'a> {
+    pub quantity: Option<&'a str>,
+    pub unit: Option<Cow<'a, str>>,
+}
+
+/// Whether the cook is busy during a timer, like `t{5 min}!active`
+/// while stirring, or free, like `t{30 min}!passive` while resting
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(any(feature = "serde", feature = "binary"), derive(serde::Serialize))]
+#[cfg_attr(feature = "binary", derive(serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub enum TimerKind {
+    /// The default when the timer is not marked
+    #[default]
+    Active,
+    Passive,
+}
+
+/// An amount relative to another ingredient, like `{salt}(2% flour)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentage<'a> {
+    /// The percentage, `2` for `2%`
+    pub percent: Quantity,
+    /// Name of the other ingredient
+    pub of: &'a str,
+}
+
+impl<'a> Amount<'a> {
+    pub const fn new(quantity: Option<&'a str>, unit: Option<&'a str>) -> Self {
+        let unit = match unit {
+            Some(unit) => Some(Cow::Borrowed(unit)),
+            None => None,
+        };
+        Self { quantity, unit }
+    }
+
+    /// Numeric value of the quantity, if it has one
+    ///
+    /// ```
+    /// use recipe_parser::{Amount, Quantity};
+    ///
+    /// let amount = Amount::new(Some("1,200"), Some("gr"));
+    /// assert_eq!(amount.quantity_value(), Quantity::new(1200, 1));
+    /// ```
+    pub fn quantity_value(&self) -> Option<Quantity> {
+        self.quantity.and_then(Quantity::parse)
+    }
+
+    /// Numeric value of the quantity, for recipes written with the given decimal mark
+    pub fn quantity_value_with_decimal_mark(&self, mark: DecimalMark) -> Option<Quantity> {
+        self.quantity
+            .and_then(|quantity| Quantity::parse_with_decimal_mark(quantity, mark))
+    }
+
+    /// Canonical unit, if it has one. The original text is kept in `unit`.
+    ///
+    /// ```
+    /// use recipe_parser::{Amount, Unit, UnitRegistry};
+    ///
+    /// let amount = Amount::new(Some("200"), Some("grams"));
+    /// assert_eq!(amount.unit_value(&UnitRegistry::new()), Some(Unit::Gram));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn unit_value(&self, registry: &UnitRegistry) -> Option<Unit> {
+        self.unit.as_deref().map(|unit| registry.resolve(unit))
+    }
+
+    /// What the amount measures, from its canonical unit. Amounts with just
+    /// a number, like `{eggs}(3)`, are a count.
+    ///
+    /// ```
+    /// use recipe_parser::{Amount, AmountKind, UnitRegistry};
+    ///
+    /// let registry = UnitRegistry::new();
+    /// assert_eq!(Amount::new(Some("200"), Some("gr")).kind(&registry), AmountKind::Weight);
+    /// assert_eq!(Amount::new(Some("2"), Some("cloves")).kind(&registry), AmountKind::Count);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn kind(&self, registry: &UnitRegistry) -> AmountKind {
+        if self.percentage().is_some() {
+            return AmountKind::Unknown;
+        }
+        match (self.quantity_value(), self.unit_value(registry)) {
+            (_, Some(unit)) => unit.kind(),
+            (Some(_), None) => AmountKind::Count,
+            (None, None) => AmountKind::Unknown,
+        }
+    }
+
+    /// The percentage of another ingredient, when the unit is like `% flour`
+    ///
+    /// ```
+    /// use recipe_parser::{Amount, Quantity};
+    ///
+    /// let amount = Amount::new(Some("2"), Some("% flour"));
+    /// let percentage = amount.percentage().unwrap();
+    /// assert_eq!(percentage.percent, Quantity::new(2, 1).unwrap());
+    /// assert_eq!(percentage.of, "flour");
+    /// ```
+    pub fn percentage(&self) -> Option<Percentage<'_>> {
+        let of = self.unit.as_deref()?.strip_prefix('%')?.trim();
+        if of.is_empty() {
+            return None;
+        }
+        Some(Percentage {
+            percent: self.quantity_value()?,
+            of,
+        })
+    }
+
+    /// Multiply the quantity by the given factor, the unit stays the same.
+    ///
+    /// Returns `None` when the quantity is missing or not numeric.
+    pub fn scale(&self, factor: Quantity) -> Option<Quantity> {
+        self.quantity_value()?.checked_mul(factor)
+    }
+}
+
+impl Display for Amount<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match (self.quantity, self.unit.as_deref()) {
+            (Some(quantity), Some(unit)) if unit.starts_with('%') => {
+                write!(f, "{}{}", quantity, unit)
+            }
+            (Some(quantity), Some(unit)) => write!(f, "{} {}", quantity, unit),
+            (Some(v), None) | (None, Some(v)) => write!(f, "{}", v),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Write all the amounts separated by ` / `, like `113 gr / 1 stick`
+pub fn display_amounts(amounts: &[Amount]) -> String {
+    amounts
+        .iter()
+        .map(|amount| amount.to_string())
+        .collect::<Vec<_>>()
+        .join(" / ")
 }
 
 impl Display for Token<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Token::Ingredient {
-                name,
-                quantity: _,
-                unit: _,
-            } => write!(f, "{}", name),
-            Token::RecipeRef {
-                name,
-                quantity: _,
-                unit: _,
-            } => write!(f, "\"{}\"", name),
+            Token::Ingredient { name, amounts: _ } => write!(f, "{}", name),
+            Token::RecipeRef { name, amounts: _ } => write!(f, "\"{}\"", name),
             Token::Backstory(v)
-            | Token::Timer(v)
+            | Token::Timer { duration: v, .. }
             | Token::Material(v)
             | Token::Word(v)
-            | Token::Space(v) => {
+            | Token::Space(v)
+            | Token::Section(v) => {
                 write!(f, "{}", v)
             }
+            Token::Link { text, url: _ } => write!(f, "{}", text),
+            Token::Image { alt, url: _ } => write!(f, "{}", alt),
             Token::Metadata { key: _, value: _ } => Ok(()),
             Token::Comment(_) => Ok(()),
         }
     }
 }
 
-pub fn recipe_value<'a>(input: &mut Input<'a>) -> ModalResult<Token<'a>> {
+/// First characters of the tokens that are not words or spaces
+const TOKEN_STARTS: [char; 14] = [
+    '>', '=', '&', '{', '@', '\r', '\n', '/', '!', '[', '(', '\\', ' ', '\t',
+];
+
+pub fn recipe_value<'a>(input: &mut Input<'a, '_>) -> ModalResult<Token<'a>> {
+    // Most of a recipe are words and spaces, which don't need to go through
+    // every alternative
+    let remaining: &str = input;
+    let mut chars = remaining.chars();
+    match (chars.next(), chars.next()) {
+        (Some(' ' | '\t'), _) => return parse_space.map(Token::Space).parse_next(input),
+        (Some(c), _)
+            if !TOKEN_STARTS.contains(&c) && !input.state.starts_with_prefix(remaining) =>
+        {
+            return parse_word.map(Token::Word).parse_next(input);
+        }
+        _ => {}
+    }
     alt((
+        // `>>>` must be tried before `>>`
+        parse_multiline_metadata.map(|(key, value)| Token::Metadata { key, value }),
         parse_metadata.map(|(key, value)| Token::Metadata { key, value }),
-        parse_material.map(|m| Token::Material(m)),
-        parse_timer.map(|t| Token::Timer(t)),
+        parse_section.map(Token::Section),
+        parse_material.map(Token::Material),
+        parse_timer.map(|(duration, kind)| Token::Timer { duration, kind }),
         // Because ingredient doesn't have a prefix before the curly braces, e.g: `&{}`
         // it must always be parsed after timer and material
-        parse_ingredient.map(|(name, amount)| {
-            let mut quantity = None;
-            let mut unit = None;
-            if let Some((_quantity, _unit)) = amount {
-                quantity = _quantity;
-                unit = _unit;
-            };
-
-            Token::Ingredient {
-                name,
-                quantity,
-                unit,
-            }
-        }),
-        parse_recipe_ref.map(|(name, amount)| {
-            let mut quantity = None;
-            let mut unit = None;
-            if let Some((_quantity, _unit)) = amount {
-                quantity = _quantity;
-                unit = _unit;
-            };
-
-            Token::RecipeRef {
-                name,
-                quantity,
-                unit,
-            }
+        parse_ingredient.map(|(name, amounts)| Token::Ingredient {
+            name: Cow::Borrowed(name),
+            amounts,
         }),
-        parse_backstory.map(|v| Token::Backstory(v)),
-        parse_comment.map(|v| Token::Comment(v)),
-        "(".map(|v| Token::Word(v)),
-        parse_word.map(|v| Token::Word(v)),
-        space1.map(|v| Token::Space(v)),
-        multispace1.map(|v| Token::Space(v)),
+        parse_recipe_ref.map(|(name, amounts)| Token::RecipeRef { name, amounts }),
+        parse_backstory.map(Token::Backstory),
+        parse_comment.map(Token::Comment),
+        // Only reached at the start of a token, urls like `https://` are consumed by `parse_word`
+        parse_line_comment.map(Token::Comment),
+        parse_image.map(|(alt, url)| Token::Image { alt, url }),
+        parse_link.map(|(text, url)| Token::Link { text, url }),
+        "(".map(Token::Word),
+        parse_escaped.map(Token::Word),
+        "\\".map(Token::Word),
+        parse_word.map(Token::Word),
+        parse_space.map(Token::Space),
     ))
     .parse_next(input)
 }
 
-pub fn recipe<'a>(input: &mut Input<'a>) -> ModalResult<Vec<Token<'a>>> {
+pub fn recipe<'a>(input: &mut Input<'a, '_>) -> ModalResult<Vec<Token<'a>>> {
     repeat(0.., recipe_value).parse_next(input)
 }
 
 /// Parse recipe tokens from a string
 ///
+/// An empty input is an empty recipe, not an error, and an input with
+/// only whitespace is a single [`Token::Space`]. Two spaces are never next
+/// to each other.
+///
 /// Example:
 ///
 /// ```
@@ -296,16 +806,289 @@ pub fn recipe<'a>(input: &mut Input<'a>) -> ModalResult<Vec<Token<'a>>> {
 ///
 /// println!("{result:?}");
 /// ```
-pub fn parse(input: &str) -> Result<Vec<Token<'_>>, ParseError<LocatingSlice<&str>, ContextError>> {
-    let input = LocatingSlice::new(input);
+pub fn parse(input: &str) -> Result<Vec<Token<'_>>, Error<'_, 'static>> {
+    parse_with_options(input, &DEFAULT_OPTIONS)
+}
+
+/// Parse recipe tokens from a string using the given options
+///
+/// Example:
+///
+/// ```
+/// use recipe_parser::{parse_with_options, ParseOptions, Token};
+///
+/// let options = ParseOptions {
+///     line_comments: false,
+///     ..Default::default()
+/// };
+/// let result = parse_with_options("//not-a-comment", &options).unwrap();
+///
+/// assert_eq!(result, vec![Token::Word("//not-a-comment")]);
+/// ```
+pub fn parse_with_options<'a, 'o>(
+    input: &'a str,
+    options: &'o ParseOptions,
+) -> Result<Vec<Token<'a>>, Error<'a, 'o>> {
+    let input = Stateful {
+        input: LocatingSlice::new(input),
+        state: options,
+    };
     recipe.parse(input)
 }
 
+/// Parse recipe tokens till the first position where `stop` returns true,
+/// returning the tokens and the rest of the input, starting at the boundary.
+/// Useful to embed a recipe in another format, like a fenced block of
+/// markdown.
+///
+/// `stop` is called with the remaining input before each token, so a
+/// boundary inside an ingredient, a link or a comment doesn't cut it in
+/// half. Words, spaces and the backstory are plain text, the boundary is
+/// looked for inside them too.
+///
+/// Example:
+///
+/// ```
+/// use recipe_parser::{parse_until, Token};
+///
+/// let input = "Add {salt}\n```\nmore markdown";
+/// let (tokens, rest) = parse_until(input, |rest| rest.starts_with("```")).unwrap();
+/// assert_eq!(tokens.len(), 4);
+/// assert_eq!(rest, "```\nmore markdown");
+/// ```
+pub fn parse_until<F>(input: &str, stop: F) -> Result<(Vec<Token<'_>>, &str), Error<'_, 'static>>
+where
+    F: FnMut(&str) -> bool,
+{
+    parse_until_with_options(input, &DEFAULT_OPTIONS, stop)
+}
+
+/// Parse recipe tokens till the first position where `stop` returns true,
+/// using the given options, see [`parse_until`]
+///
+/// Example:
+///
+/// ```
+/// use recipe_parser::{parse_until_with_options, ParseOptions, Token};
+///
+/// let options = ParseOptions {
+///     line_comments: false,
+///     ..Default::default()
+/// };
+/// let (tokens, rest) =
+///     parse_until_with_options("//not-a-comment\n~~~", &options, |rest| rest.starts_with("~~~"))
+///         .unwrap();
+/// assert_eq!(tokens, vec![Token::Word("//not-a-comment"), Token::Space("\n")]);
+/// assert_eq!(rest, "~~~");
+/// ```
+pub fn parse_until_with_options<'a, 'o, F>(
+    input: &'a str,
+    options: &'o ParseOptions,
+    mut stop: F,
+) -> Result<(Vec<Token<'a>>, &'a str), Error<'a, 'o>>
+where
+    F: FnMut(&str) -> bool,
+{
+    let mut stream = Stateful {
+        input: LocatingSlice::new(input),
+        state: options,
+    };
+    let mut tokens = vec![];
+    loop {
+        let start = input.len() - stream.eof_offset();
+        if start == input.len() || stop(&input[start..]) {
+            return Ok((tokens, &input[start..]));
+        }
+        let Ok(token) = recipe_value.parse_next(&mut stream) else {
+            // Parse again from the failing token, to get the error `parse` returns
+            let skip = |input: &mut Input<'a, 'o>| {
+                input.next_slice(start);
+                Ok(())
+            };
+            let stream = Stateful {
+                input: LocatingSlice::new(input),
+                state: options,
+            };
+            return Err(preceded(skip, recipe)
+                .parse(stream)
+                .expect_err("the token failed to parse"));
+        };
+        let end = input.len() - stream.eof_offset();
+        // Where the plain text of the token starts, the boundary can be inside it
+        let text = match token {
+            Token::Word(_) | Token::Space(_) => Some(start),
+            Token::Backstory(story) => Some(end - story.len()),
+            _ => None,
+        };
+        let boundary = text.and_then(|text| {
+            input[text..end]
+                .char_indices()
+                .map(|(offset, _)| text + offset)
+                .find(|offset| *offset > start && stop(&input[*offset..]))
+        });
+        let Some(boundary) = boundary else {
+            tokens.push(token);
+            continue;
+        };
+        tokens.push(match token {
+            Token::Word(_) => Token::Word(&input[start..boundary]),
+            Token::Space(_) => Token::Space(&input[start..boundary]),
+            _ => Token::Backstory(&input[text.unwrap_or(start)..boundary]),
+        });
+        return Ok((tokens, &input[boundary..]));
+    }
+}
+
+/// Call `f` with each token as soon as it's parsed, without collecting them.
+///
+/// Parsing stops when `f` returns [`ControlFlow::Break`], and the input after
+/// the last token is returned. It's empty when the whole input was parsed.
+/// The error is the same [`parse`] would return.
+///
+/// Example:
+///
+/// ```
+/// use std::ops::ControlFlow;
+/// use recipe_parser::{parse_each, Token};
+///
+/// let input = ">> name: soup\nBoil the {potatoes}(3) with {salt}";
+/// let mut first = None;
+/// let rest = parse_each(input, |token| match token {
+///     Token::Ingredient { name, .. } => {
+///         first = Some(name);
+///         ControlFlow::Break(())
+///     }
+///     _ => ControlFlow::Continue(()),
+/// })
+/// .unwrap();
+/// assert_eq!(first.as_deref(), Some("potatoes"));
+/// assert_eq!(rest, " with {salt}");
+/// ```
+pub fn parse_each<'a, F>(input: &'a str, f: F) -> Result<&'a str, Error<'a, 'static>>
+where
+    F: FnMut(Token<'a>) -> ControlFlow<()>,
+{
+    parse_each_with_options(input, &DEFAULT_OPTIONS, f)
+}
+
+/// Call `f` with each token as soon as it's parsed, using the given
+/// options, see [`parse_each`]
+///
+/// Example:
+///
+/// ```
+/// use std::ops::ControlFlow;
+/// use recipe_parser::{parse_each_with_options, ParseOptions, Token};
+///
+/// let options = ParseOptions {
+///     material_prefix: "#".into(),
+///     ..Default::default()
+/// };
+/// let mut materials = vec![];
+/// parse_each_with_options("Heat a #{pan} and a &{pot}", &options, |token| {
+///     if let Token::Material(name) = token {
+///         materials.push(name);
+///     }
+///     ControlFlow::Continue(())
+/// })
+/// .unwrap();
+/// assert_eq!(materials, vec!["pan"]);
+/// ```
+pub fn parse_each_with_options<'a, 'o, F>(
+    input: &'a str,
+    options: &'o ParseOptions,
+    mut f: F,
+) -> Result<&'a str, Error<'a, 'o>>
+where
+    F: FnMut(Token<'a>) -> ControlFlow<()>,
+{
+    let source = input;
+    let mut input = Stateful {
+        input: LocatingSlice::new(source),
+        state: options,
+    };
+    while input.eof_offset() > 0 {
+        let offset = source.len() - input.eof_offset();
+        let Ok(token) = recipe_value.parse_next(&mut input) else {
+            // Parse again from the failing token, to get the error `parse` returns
+            let skip = |input: &mut Input<'a, '_>| {
+                input.next_slice(offset);
+                Ok(())
+            };
+            let input = Stateful {
+                input: LocatingSlice::new(source),
+                state: options,
+            };
+            return Err(preceded(skip, recipe)
+                .parse(input)
+                .expect_err("the token failed to parse"));
+        };
+        if f(token).is_break() {
+            break;
+        }
+    }
+    let rest: &str = &input;
+    Ok(rest)
+}
+
+/// Parse recipe tokens together with the byte range they cover in the input
+///
+/// Example:
+///
+/// ```
+/// use recipe_parser::{parse_spanned, Token};
+///
+/// let tokens = parse_spanned("Add {salt}").unwrap();
+/// assert_eq!(tokens[2], (Token::Ingredient { name: "salt".into(), amounts: vec![] }, 4..10));
+/// ```
+pub fn parse_spanned(input: &str) -> Result<Vec<(Token<'_>, Range<usize>)>, Error<'_, 'static>> {
+    parse_spanned_with_options(input, &DEFAULT_OPTIONS)
+}
+
+/// Parse recipe tokens with their byte range, using the given options
+pub fn parse_spanned_with_options<'a, 'o>(
+    input: &'a str,
+    options: &'o ParseOptions,
+) -> Result<Vec<(Token<'a>, Range<usize>)>, Error<'a, 'o>> {
+    let input = Stateful {
+        input: LocatingSlice::new(input),
+        state: options,
+    };
+    repeat(0.., recipe_value.with_span()).parse(input)
+}
+
+/// Tokens with their byte range till the first error, used where a partial
+/// result is better than none, like while the recipe is being typed
+pub(crate) fn parse_spanned_partial<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Vec<(Token<'a>, Range<usize>)> {
+    let mut input = Stateful {
+        input: LocatingSlice::new(input),
+        state: options,
+    };
+    let mut tokens = vec![];
+    while input.eof_offset() > 0 {
+        match recipe_value.with_span().parse_next(&mut input) {
+            Ok(token) => tokens.push(token),
+            Err(_) => break,
+        }
+    }
+    tokens
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use rstest::*;
 
+    fn new_input(input: &str) -> Input<'_, 'static> {
+        Stateful {
+            input: LocatingSlice::new(input),
+            state: &DEFAULT_OPTIONS,
+        }
+    }
+
     #[rstest]
     #[case("salt", "salt")]
     #[case("sweet potato", "sweet potato")]
@@ -319,8 +1102,15 @@ mod test {
     #[case("10%", "10%")]
     #[case("#vegan", "#vegan")]
     #[case("mango's", "mango's")]
+    #[case("salt & pepper", "salt & pepper")]
+    #[case("50/50 blend", "50/50 blend")]
+    #[case("jalapeño", "jalapeño")]
+    #[case("vitamin b12", "vitamin b12")]
+    #[case("salt+pepper", "salt+pepper")]
+    #[case("yum!", "yum!")]
+    #[case("note: fresh", "note: fresh")]
     fn test_parse_valid_string(#[case] input: String, #[case] expected: &str) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let valid_str = parse_valid_string(&mut input).unwrap();
         assert_eq!(valid_str, expected)
     }
@@ -330,14 +1120,25 @@ mod test {
     #[case("/* hello */", "hello")]
     #[case("/* multi\nline\ncomment */", "multi\nline\ncomment")]
     fn test_parse_comment_ok(#[case] input: String, #[case] expected: &str) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let comment = parse_comment(&mut input).expect("failed to parse comment");
         assert_eq!(comment, expected)
     }
 
+    #[rstest]
+    #[case("//", "")]
+    #[case("// taste and adjust", "taste and adjust")]
+    #[case("//no space", "no space")]
+    #[case("// till the end\nnext line", "till the end")]
+    fn test_parse_line_comment_ok(#[case] input: String, #[case] expected: &str) {
+        let mut input = new_input(input.as_str());
+        let comment = parse_line_comment(&mut input).expect("failed to parse line comment");
+        assert_eq!(comment, expected)
+    }
+
     #[test]
     fn test_parse_comment_wrong() {
-        let mut input = LocatingSlice::new("/* unclosed");
+        let mut input = new_input("/* unclosed");
         let res = parse_comment(&mut input);
         assert!(res.is_err());
 
@@ -352,19 +1153,40 @@ mod test {
     #[case("{smashed potatoes}", "smashed potatoes")]
     #[case("{15 minutes}", "15 minutes")]
     #[case("{   15 minutes  }", "15 minutes")]
+    #[case("{salt & pepper}", "salt & pepper")]
+    #[case("{vitamin b12}", "vitamin b12")]
     fn test_parse_curly_ok(#[case] input: String, #[case] expected: &str) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let content = parse_curly(&mut input).expect("to work");
         assert_eq!(expected, content);
     }
 
+    #[rstest]
+    #[case("{sweet\npotato}")]
+    #[case("{sweet\r\npotato}")]
+    #[case("{sweet potato\n}")]
+    fn test_parse_curly_multiline_name_fails(#[case] input: &str) {
+        let mut input = new_input(input);
+        let err = parse_curly(&mut input).unwrap_err();
+        let winnow::error::ErrMode::Cut(err) = err else {
+            panic!("expected a cut error");
+        };
+        assert_eq!(err.to_string(), format!("expected {SAME_LINE_CURLY}"));
+    }
+
+    #[test]
+    fn test_parse_curly_keeps_tabs() {
+        let mut input = new_input("{sweet\tpotato}");
+        assert_eq!(parse_curly(&mut input).unwrap(), "sweet\tpotato");
+    }
+
     #[test]
     fn test_parse_curly_wrong() {
-        let mut input = LocatingSlice::new("{}");
+        let mut input = new_input("{}");
         let res = parse_curly(&mut input);
         assert!(res.is_err());
 
-        let mut input = LocatingSlice::new("{unclosed");
+        let mut input = new_input("{unclosed");
         let res = parse_curly(&mut input);
         assert!(res.is_err());
 
@@ -372,6 +1194,52 @@ mod test {
         assert!(matches!(err, winnow::error::ErrMode::Cut(_)));
     }
 
+    #[rstest]
+    #[case("\\{", "{")]
+    #[case("\\}", "}")]
+    #[case("\\\\", "\\")]
+    #[case("\\/", "/")]
+    fn test_parse_escaped_ok(#[case] input: &str, #[case] expected: &str) {
+        let mut input = new_input(input);
+        let escaped = parse_escaped(&mut input).expect("failed to parse escaped");
+        assert_eq!(escaped, expected)
+    }
+
+    #[rstest]
+    #[case("\\{salt}", vec![Token::Word("{"), Token::Word("salt}")])]
+    #[case("t\\{5 min}", vec![Token::Word("t"), Token::Word("{"), Token::Word("5"), Token::Space(" "), Token::Word("min}")])]
+    #[case("\\// hi", vec![Token::Word("/"), Token::Word("/"), Token::Space(" "), Token::Word("hi")])]
+    #[case("a\\b", vec![Token::Word("a"), Token::Word("\\"), Token::Word("b")])]
+    fn test_parse_escaped_in_recipe(#[case] input: &str, #[case] expected: Vec<Token>) {
+        let tokens = parse(input).expect("failed to parse");
+        assert_eq!(tokens, expected)
+    }
+
+    #[rstest]
+    #[case("{{a}}", 1)]
+    #[case("{a{b}}", 2)]
+    #[case("{tomato {cherry}}", 8)]
+    fn test_parse_nested_curly_fails(#[case] input: &str, #[case] offset: usize) {
+        let err = parse(input).unwrap_err();
+        println!("{err}");
+        assert_eq!(err.offset(), offset);
+    }
+
+    #[test]
+    fn test_parse_stray_closing_curly_is_a_word() {
+        let tokens = parse("{a}b}").expect("failed to parse");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Ingredient {
+                    name: "a".into(),
+                    amounts: vec![]
+                },
+                Token::Word("b}")
+            ]
+        );
+    }
+
     #[rstest]
     #[case("200", "200")]
     #[case("2.1", "2.1")]
@@ -381,7 +1249,7 @@ mod test {
     #[case("1/2", "1/2")]
     #[case(".2", ".2")]
     fn test_parse_quantity_ok(#[case] input: String, #[case] expected: &str) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let content = parse_quantity(&mut input).expect("to work");
         assert_eq!(expected, content);
     }
@@ -393,39 +1261,38 @@ mod test {
     #[case("2//0")]
     fn test_parse_quantity_invalid(#[case] input: String) {
         // TODO: Add verify function to validate the last char
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let res = parse_quantity(&mut input);
         let err = res.unwrap_err();
         assert!(matches!(err, winnow::error::ErrMode::Cut(_)));
     }
 
     #[rstest]
-    #[case("(200gr)", (Some("200"), Some("gr")))]
-    #[case("(1/2)", (Some("1/2"), None))]
-    #[case("(100 gr)", (Some("100"), Some("gr")))]
-    #[case("(10 ml)", (Some("10"), Some("ml")))]
-    #[case("( 10 ml )", (Some("10"), Some("ml")))]
-    #[case("(1.5 cups)", (Some("1.5"), Some("cups")))]
-    fn test_parse_ingredient_amount_ok(
-        #[case] input: String,
-        #[case] expected: (Option<&str>, Option<&str>),
-    ) {
-        let mut input = LocatingSlice::new(input.as_str());
+    #[case("(200gr)", vec![Amount::new(Some("200"), Some("gr"))])]
+    #[case("(1/2)", vec![Amount::new(Some("1/2"), None)])]
+    #[case("(100 gr)", vec![Amount::new(Some("100"), Some("gr"))])]
+    #[case("(10 ml)", vec![Amount::new(Some("10"), Some("ml"))])]
+    #[case("( 10 ml )", vec![Amount::new(Some("10"), Some("ml"))])]
+    #[case("(1.5 cups)", vec![Amount::new(Some("1.5"), Some("cups"))])]
+    #[case("(113gr / 1 stick)", vec![Amount::new(Some("113"), Some("gr")), Amount::new(Some("1"), Some("stick"))])]
+    #[case("(1/2 cup/120 ml)", vec![Amount::new(Some("1/2"), Some("cup")), Amount::new(Some("120"), Some("ml"))])]
+    #[case("(1 / 2)", vec![Amount::new(Some("1"), None), Amount::new(Some("2"), None)])]
+    #[case("()", vec![])]
+    #[case("(  )", vec![])]
+    fn test_parse_ingredient_amount_ok(#[case] input: String, #[case] expected: Vec<Amount>) {
+        let mut input = new_input(input.as_str());
         let content = parse_ingredient_amount(&mut input).expect("to work");
         assert_eq!(expected, content);
     }
 
     #[rstest]
-    #[case("()")]
     #[case("(unclosed")]
+    #[case("( / )")]
     fn test_parse_ingredient_amount_invalid_quantity(#[case] input: String) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let res = parse_ingredient_amount(&mut input);
         match res {
-            Ok(_) => {
-                // should fail the test
-                assert!(false);
-            }
+            Ok(_) => panic!("amount should not be parsed"),
             Err(e) => match e {
                 winnow::error::ErrMode::Cut(err) => {
                     println!("{}", err);
@@ -433,11 +1300,8 @@ mod test {
                         "expected a quantity value, like 3, 1.2, 1/2 or 1_000",
                         err.to_string()
                     );
-                    assert!(true);
-                }
-                _ => {
-                    assert!(false);
                 }
+                _ => panic!("expected a cut error"),
             },
         }
     }
@@ -445,70 +1309,143 @@ mod test {
     #[rstest]
     #[case("(1.5")]
     fn test_parse_ingredient_amount_invalid_amount(#[case] input: String) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let res = parse_ingredient_amount(&mut input);
         match res {
-            Ok(_) => {
-                // should fail the test
-                assert!(false);
-            }
+            Ok(_) => panic!("amount should not be parsed"),
             Err(e) => match e {
                 winnow::error::ErrMode::Cut(err) => {
                     println!("{}", err);
                     assert_eq!("expected `)`", err.to_string());
-                    assert!(true);
-                }
-                _ => {
-                    assert!(false);
                 }
+                _ => panic!("expected a cut error"),
             },
         }
     }
 
     #[rstest]
-    #[case("{sweet potato}(200gr)", "sweet potato", Some((Some("200"),Some("gr"))))]
-    #[case("{sweet potato}", "sweet potato", None)]
+    #[case("{sweet potato}(200gr)", "sweet potato", vec![Amount::new(Some("200"), Some("gr"))])]
+    #[case("{sweet potato}", "sweet potato", vec![])]
+    #[case("{salt}()", "salt", vec![])]
     fn test_parse_ingredient_ok(
         #[case] input: String,
         #[case] expected_ingredient: &str,
-        #[case] expected_amount: Option<(Option<&str>, Option<&str>)>,
+        #[case] expected_amount: Vec<Amount>,
     ) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let (ingredient, amount) = parse_ingredient(&mut input).unwrap();
         assert_eq!(expected_ingredient, ingredient);
         assert_eq!(expected_amount, amount);
     }
 
+    #[rstest]
+    #[case(vec![Amount::new(Some("113"), Some("gr")), Amount::new(Some("1"), Some("stick"))], "113 gr / 1 stick")]
+    #[case(vec![Amount::new(Some("2"), None)], "2")]
+    #[case(vec![Amount::new(Some("2"), Some("% flour"))], "2% flour")]
+    #[case(vec![], "")]
+    fn test_display_amounts(#[case] amounts: Vec<Amount>, #[case] expected: &str) {
+        assert_eq!(display_amounts(&amounts), expected);
+    }
+
+    #[test]
+    fn test_amount_scale_every_alternative() {
+        let amounts = [
+            Amount::new(Some("113"), Some("gr")),
+            Amount::new(Some("1/2"), Some("stick")),
+            Amount::new(None, Some("pinch")),
+        ];
+        let factor = Quantity::new(2, 1).unwrap();
+        let scaled: Vec<_> = amounts.iter().map(|a| a.scale(factor)).collect();
+        assert_eq!(
+            scaled,
+            vec![Quantity::new(226, 1), Quantity::new(1, 1), None]
+        );
+    }
+
+    #[rstest]
+    #[case("(2% flour)", Some((Quantity::new(2, 1), "flour")))]
+    #[case("(1.5 % whole wheat flour)", Some((Quantity::new(3, 2), "whole wheat flour")))]
+    #[case("(10%)", None)]
+    #[case("(2 gr)", None)]
+    fn test_amount_percentage(
+        #[case] input: &str,
+        #[case] expected: Option<(Option<Quantity>, &str)>,
+    ) {
+        let amounts = parse_ingredient_amount(&mut new_input(input)).unwrap();
+        let percentage = amounts[0]
+            .percentage()
+            .map(|percentage| (Some(percentage.percent), percentage.of));
+        assert_eq!(percentage, expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[rstest]
+    #[case(Amount::new(Some("200"), Some("gr")), AmountKind::Weight)]
+    #[case(Amount::new(Some("1"), Some("lb")), AmountKind::Weight)]
+    #[case(Amount::new(Some("2"), Some("cups")), AmountKind::Volume)]
+    #[case(Amount::new(Some("1"), Some("Tbsp.")), AmountKind::Volume)]
+    #[case(Amount::new(Some("3"), None), AmountKind::Count)]
+    #[case(Amount::new(Some("2"), Some("cloves")), AmountKind::Count)]
+    #[case(Amount::new(Some("1"), Some("large")), AmountKind::Count)]
+    #[case(Amount::new(Some("2"), Some("% flour")), AmountKind::Unknown)]
+    #[case(Amount::new(None, None), AmountKind::Unknown)]
+    fn test_amount_kind(#[case] amount: Amount, #[case] expected: AmountKind) {
+        assert_eq!(amount.kind(&UnitRegistry::new()), expected);
+    }
+
+    #[rstest]
+    #[case("(1_200 gr)", DecimalMark::Point, 1200)]
+    #[case("(1,200 gr)", DecimalMark::Point, 1200)]
+    #[case("(1.200 gr)", DecimalMark::Comma, 1200)]
+    #[case("(1_200 gr)", DecimalMark::Comma, 1200)]
+    fn test_amount_with_thousands(
+        #[case] input: &str,
+        #[case] mark: DecimalMark,
+        #[case] expected: i64,
+    ) {
+        let amounts = parse_ingredient_amount(&mut new_input(input)).unwrap();
+        assert_eq!(
+            amounts[0].quantity_value_with_decimal_mark(mark),
+            Quantity::new(expected, 1)
+        );
+    }
+
     #[rstest]
     #[case("&{pot}", "pot")]
     #[case("&{small jar}", "small jar")]
     #[case("&{stick}", "stick")]
     #[case("&{bricks}", "bricks")]
     fn test_parse_material_ok(#[case] input: String, #[case] expected: &str) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let material = parse_material(&mut input).expect("Failed to parse material");
         assert_eq!(material, expected)
     }
 
     #[rstest]
-    #[case("t{1 minute}", "1 minute")]
-    #[case("t{2 hours}", "2 hours")]
-    fn test_parse_timer_ok(#[case] input: String, #[case] expected: &str) {
-        let mut input = LocatingSlice::new(input.as_str());
+    #[case("t{1 minute}", ("1 minute", TimerKind::Active))]
+    #[case("t{2 hours}", ("2 hours", TimerKind::Active))]
+    #[case("t{5 minutes}!active", ("5 minutes", TimerKind::Active))]
+    #[case("t{30 minutes}!passive", ("30 minutes", TimerKind::Passive))]
+    #[case("t{30 minutes}!passively", ("30 minutes", TimerKind::Active))]
+    #[case("t{30 minutes}!", ("30 minutes", TimerKind::Active))]
+    fn test_parse_timer_ok(#[case] input: String, #[case] expected: (&str, TimerKind)) {
+        let mut input = new_input(input.as_str());
         let timer = parse_timer(&mut input).expect("Failed to parse timer");
         assert_eq!(timer, expected)
     }
 
     #[rstest]
-    #[case("@{woile/tomato-sauce}(200gr)", "woile/tomato-sauce", Some((Some("200"),Some("gr"))))]
-    #[case("@{woile/tomato-sauce}", "woile/tomato-sauce", None)]
-    #[case("@{special stew}", "special stew", None)]
+    #[case("@{woile/tomato-sauce}(200gr)", "woile/tomato-sauce", vec![Amount::new(Some("200"), Some("gr"))])]
+    #[case("@{woile/tomato-sauce}", "woile/tomato-sauce", vec![])]
+    #[case("@{special stew}", "special stew", vec![])]
+    #[case("@recipe{pizza dough}", "pizza dough", vec![])]
+    #[case("@recipe{pizza dough}(2)", "pizza dough", vec![Amount::new(Some("2"), None)])]
     fn test_parse_recipe_ok(
         #[case] input: String,
         #[case] expected_recipe: &str,
-        #[case] expected_amount: Option<(Option<&str>, Option<&str>)>,
+        #[case] expected_amount: Vec<Amount>,
     ) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let (recipe, amount) = parse_recipe_ref(&mut input).unwrap();
         assert_eq!(expected_recipe, recipe);
         assert_eq!(expected_amount, amount);
@@ -522,37 +1459,113 @@ mod test {
     #[case(">>    key:     pepe\n", ("key", "pepe"))]
     #[case(">>    key:\t\tpepe\n", ("key", "pepe"))]
     #[case(">>    key:pepe\n", ("key", "pepe"))]
+    #[case(">> key: pepe\r\n", ("key", "pepe"))]
+    #[case(">> key: pepe", ("key", "pepe"))]
     fn test_parse_metadata_ok(#[case] input: String, #[case] expected: (&str, &str)) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let metadata = parse_metadata(&mut input).expect("Failed to parse metadata");
         assert_eq!(metadata, expected)
     }
 
+    #[rstest]
+    #[case(">>> description: one line", ("description", "one line"))]
+    #[case(">>> description: line one\nline two\n", ("description", "line one\nline two"))]
+    #[case(">>>description:\n  indented\n\n  paragraphs\n", ("description", "indented\n\n  paragraphs"))]
+    fn test_parse_multiline_metadata_ok(#[case] input: &str, #[case] expected: (&str, &str)) {
+        let mut input = new_input(input);
+        let metadata = parse_multiline_metadata(&mut input).expect("Failed to parse metadata");
+        assert_eq!(metadata, expected)
+    }
+
+    #[test]
+    fn test_parse_multiline_metadata_stops_at_directives() {
+        let input = ">>> description: line one\nline two\n>> tags: vegan\nBoil\n---\nstory";
+        let tokens = parse(input).expect("failed to parse");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Metadata {
+                    key: "description",
+                    value: "line one\nline two"
+                },
+                Token::Metadata {
+                    key: "tags",
+                    value: "vegan"
+                },
+                Token::Word("Boil"),
+                Token::Backstory("story"),
+            ]
+        );
+
+        let input = ">>> notes: a\nb\n\n---\nstory";
+        let tokens = parse(input).expect("failed to parse");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Metadata {
+                    key: "notes",
+                    value: "a\nb"
+                },
+                Token::Backstory("story"),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("= For the dough", vec![Token::Section("For the dough")])]
+    #[case("== Filling ==\nMix", vec![Token::Section("Filling"), Token::Space("\n"), Token::Word("Mix")])]
+    #[case("Mix\n  = Bake", vec![Token::Word("Mix"), Token::Space("\n  "), Token::Section("Bake")])]
+    #[case("2 = 1 + 1", vec![Token::Word("2"), Token::Space(" "), Token::Word("="), Token::Space(" "), Token::Word("1"), Token::Space(" "), Token::Word("+"), Token::Space(" "), Token::Word("1")])]
+    #[case("=\nMix", vec![Token::Word("="), Token::Space("\n"), Token::Word("Mix")])]
+    fn test_parse_section(#[case] input: &str, #[case] expected: Vec<Token>) {
+        let tokens = parse(input).expect("failed to parse");
+        assert_eq!(tokens, expected);
+    }
+
+    #[rstest]
+    #[case("[this](https://example.com)", vec![Token::Link { text: "this", url: "https://example.com" }])]
+    #[case("like [this one](fold.html).", vec![Token::Word("like"), Token::Space(" "), Token::Link { text: "this one", url: "fold.html" }, Token::Word(".")])]
+    #[case("![folded dumplings](dumplings.png)", vec![Token::Image { alt: "folded dumplings", url: "dumplings.png" }])]
+    #[case("see https://example.com", vec![Token::Word("see"), Token::Space(" "), Token::Word("https://example.com")])]
+    #[case("[not a link]", vec![Token::Word("[not"), Token::Space(" "), Token::Word("a"), Token::Space(" "), Token::Word("link]")])]
+    #[case("[spaced](a url)", vec![Token::Word("[spaced](a"), Token::Space(" "), Token::Word("url)")])]
+    #[case("wow![a](b)", vec![Token::Word("wow![a](b)")])]
+    #[case("\\[a](b)", vec![Token::Word("["), Token::Word("a](b)")])]
+    fn test_parse_links(#[case] input: &str, #[case] expected: Vec<Token>) {
+        let tokens = parse(input).expect("failed to parse");
+        assert_eq!(tokens, expected);
+    }
+
     #[rstest]
     #[case("\n---\nwhat a backstory", "what a backstory")]
     #[case("\n   ---\nwhat a backstory", "what a backstory")]
     #[case("\n   ---\n\nwhat a backstory", "what a backstory")]
     #[case("\n   ---\n\nthis is **markdown**", "this is **markdown**")]
     #[case("\n   ---\n\nthis is [markdown](url)", "this is [markdown](url)")]
+    #[case("\n---    \nwhat a backstory", "what a backstory")]
+    #[case("\n--- \t\r\nwhat a backstory", "what a backstory")]
+    #[case("\n---", "")]
     fn test_parse_backstory_ok(#[case] input: String, #[case] expected: &str) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let backsotry = parse_backstory(&mut input).expect("failed to parse backstory");
         assert_eq!(backsotry, expected)
     }
 
     #[rstest]
-    #[case("\n---    \nwhat a backstory")]
+    #[case("\n--- not a backstory")]
+    #[case("\n----\nnot a backstory")]
+    #[case("--- no newline before")]
     fn test_parse_backstory_fail(#[case] input: String) {
-        let mut input = LocatingSlice::new(input.as_str());
+        let mut input = new_input(input.as_str());
         let out = parse_backstory(&mut input);
         assert!(out.is_err())
     }
 
     #[rstest]
     #[case(" ", Token::Space(" "))]
-    #[case("{holis}(100 gr)", Token::Ingredient { name: "holis", quantity: Some("100"), unit: Some("gr") })]
+    #[case("{holis}(100 gr)", Token::Ingredient { name: "holis".into(), amounts: vec![Amount::new(Some("100"), Some("gr"))] })]
     fn test_recipe_value_ok(#[case] input: &str, #[case] expected: Token) {
-        let mut input = LocatingSlice::new(input);
+        let mut input = new_input(input);
         let token = recipe_value(&mut input).expect("failed to parse token");
         assert_eq!(token, expected)
     }
@@ -561,12 +1574,8 @@ mod test {
     fn test_recipe_ok() {
         let input = "Boil the quinoa for t{5 minutes} in a &{pot}.\nPut the boiled {quinoa}(200gr) in the base of the bowl.";
         let expected = "Boil the quinoa for 5 minutes in a pot.\nPut the boiled quinoa in the base of the bowl.";
-        let recipe = recipe
-            .parse(LocatingSlice::new(input))
-            .expect("parse failed");
-        let fmt_recipe = recipe
-            .iter()
-            .fold(String::new(), |acc, val| format!("{acc}{val}"));
+        let recipe = recipe.parse(new_input(input)).expect("parse failed");
+        let fmt_recipe: String = recipe.iter().map(|token| token.to_string()).collect();
         println!("{}", fmt_recipe);
 
         assert_eq!(expected, fmt_recipe);
@@ -577,11 +1586,13 @@ mod test {
     #[case(" ", vec![Token::Space(" ")])]
     #[case("\n\nhello", vec![Token::Space("\n\n"), Token::Word("hello")])]
     #[case("hello\n", vec![Token::Word("hello"), Token::Space("\n")])]
-    #[case(">> tags: hello\n\nhello", vec![Token::Metadata {key: "tags", value: "hello"}, Token::Space("\n\n"), Token::Word("hello")])]
-    #[case(">> source: https://hello.com\n>> tags: hello\n", vec![Token::Metadata {key: "source", value: "https://hello.com"}, Token::Space("\n"), Token::Metadata {key: "tags", value: "hello"}, Token::Space("\n")])]
-    #[case("{holis}(100 gr)", vec![Token::Ingredient { name: "holis", quantity: Some("100"), unit: Some("gr") }])]
+    #[case(">> tags: hello\n\nhello", vec![Token::Metadata {key: "tags", value: "hello"}, Token::Space("\n"), Token::Word("hello")])]
+    #[case(">> source: https://hello.com\n>> tags: hello\n", vec![Token::Metadata {key: "source", value: "https://hello.com"}, Token::Metadata {key: "tags", value: "hello"}])]
+    #[case("{holis}(100 gr)", vec![Token::Ingredient { name: "holis".into(), amounts: vec![Amount::new(Some("100"), Some("gr"))] }])]
+    #[case("Boil // taste\nServe", vec![Token::Word("Boil"), Token::Space(" "), Token::Comment("taste"), Token::Space("\n"), Token::Word("Serve")])]
+    #[case("see https://hello.com", vec![Token::Word("see"), Token::Space(" "), Token::Word("https://hello.com")])]
     fn test_recipe_cases_ok(#[case] input: &str, #[case] expected: Vec<Token>) {
-        let mut input = LocatingSlice::new(input);
+        let mut input = new_input(input);
         let token = recipe(&mut input).expect("failed to parse token");
         assert_eq!(token, expected)
     }
@@ -603,14 +1614,88 @@ mod test {
         assert!(recipe_result.is_ok());
     }
 
+    #[rstest]
+    #[case("tea t{5 min}", vec![Token::Word("tea"), Token::Space(" "), Token::Timer {
+                duration: "5 min",
+                kind: TimerKind::Active
+            }])]
+    #[case("t {salt}", vec![Token::Word("t"), Token::Space(" "), Token::Ingredient { name: "salt".into(), amounts: vec![] }])]
+    #[case("at{5 min}", vec![Token::Word("at{5"), Token::Space(" "), Token::Word("min}")])]
+    #[case(" \t=", vec![Token::Space(" \t"), Token::Word("=")])]
+    #[case("café\n---\nstory", vec![Token::Word("café"), Token::Backstory("story")])]
+    fn test_words_and_spaces_fast_path(#[case] input: &str, #[case] expected: Vec<Token>) {
+        assert_eq!(parse(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_each_visits_every_token() {
+        let input = ">> name: soup\nBoil the {potatoes}(3)\n---\nA story";
+        let mut tokens = vec![];
+        let rest = parse_each(input, |token| {
+            tokens.push(token);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(tokens, parse(input).unwrap());
+    }
+
+    #[test]
+    fn test_parse_each_stops_early() {
+        let mut count = 0;
+        let rest = parse_each(">> a: 1\n>> b: 2\n{salt {", |_| {
+            count += 1;
+            if count == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+        assert_eq!(rest, "{salt {");
+    }
+
+    #[test]
+    fn test_parse_each_with_options() {
+        let options = ParseOptions {
+            timer_prefix: "timer".into(),
+            arithmetic: true,
+            extra_symbols: "()".into(),
+            ..Default::default()
+        };
+        let input = "Add {eggs}(2*3) and {salt (fine)} for timer{5 min}";
+        let mut tokens = vec![];
+        let rest = parse_each_with_options(input, &options, |token| {
+            tokens.push(token);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(tokens, parse_with_options(input, &options).unwrap());
+        assert!(tokens.contains(&Token::Timer {
+            duration: "5 min",
+            kind: TimerKind::Active
+        }));
+    }
+
+    #[rstest]
+    #[case("Boil {salt")]
+    #[case("Boil {{salt}}")]
+    #[case("Boil the {water}.\n\tAdd the {salt (1 pinch)}")]
+    #[case("{salt}(1 pinch")]
+    fn test_parse_each_error_matches_parse(#[case] input: &str) {
+        let err = parse_each(input, |_| ControlFlow::Continue(())).unwrap_err();
+        let expected = parse(input).unwrap_err();
+        assert_eq!(err.offset(), expected.offset());
+        assert_eq!(err.to_string(), expected.to_string());
+    }
+
     #[test]
     fn test_parse_ok() {
         let input = "Boil the quinoa for t{5 minutes} in a &{pot}.\nPut the boiled {quinoa}(200gr) in the base of the bowl.";
         let expected = "Boil the quinoa for 5 minutes in a pot.\nPut the boiled quinoa in the base of the bowl.";
         let recipe = parse(input).expect("parse failed");
-        let fmt_recipe = recipe
-            .iter()
-            .fold(String::new(), |acc, val| format!("{acc}{val}"));
+        let fmt_recipe: String = recipe.iter().map(|token| token.to_string()).collect();
         println!("{}", fmt_recipe);
 
         assert_eq!(expected, fmt_recipe);
@@ -633,13 +1718,443 @@ mod test {
         println!("{:?}", recipe);
     }
 
+    #[rstest]
+    #[case("", vec![])]
+    #[case(" ", vec![Token::Space(" ")])]
+    #[case("\n", vec![Token::Space("\n")])]
+    #[case("\r\n\t \n", vec![Token::Space("\r\n\t \n")])]
+    #[case(" \t\r\n \n", vec![Token::Space(" \t\r\n \n")])]
+    fn test_parse_empty_input(#[case] input: &str, #[case] expected: Vec<Token>) {
+        assert_eq!(parse(input).unwrap(), expected);
+        let spanned: Vec<Token> = parse_spanned(input)
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(spanned, expected);
+        assert_eq!(parse_until(input, |_| false).unwrap(), (expected, ""));
+        let mut count = 0;
+        assert_eq!(
+            parse_each(input, |_| {
+                count += 1;
+                ControlFlow::Continue(())
+            }),
+            Ok("")
+        );
+        assert_eq!(count, input.len().min(1));
+    }
+
+    #[rstest]
+    #[case("a \n b")]
+    #[case("a \r\n b")]
+    #[case("a\t\n\n c")]
+    #[case("  \t Boil\t \r\n\r\n\t the {water}  \n\n")]
+    #[case("Boil /* gently */ \n\n the {water} // hot \n \n")]
+    #[case("Boil \n>> servings: 2\n \n\t= Serve\n \n")]
+    #[case(">>> notes: a\nb \n>> tags: c \n\n Boil")]
+    #[case("Boil \t \n \n---\n story")]
+    #[case("a \r \n\r\n\t b")]
+    fn test_parse_spaces_are_never_adjacent(#[case] input: &str) {
+        let tokens = parse(input).unwrap();
+        assert!(
+            !tokens
+                .windows(2)
+                .any(|pair| matches!(pair, [Token::Space(_), Token::Space(_)])),
+            "{tokens:?}"
+        );
+    }
+
+    #[test]
+    fn test_parse_space_stops_before_backstory() {
+        assert_eq!(
+            parse("Boil \t \n \n---\nstory").unwrap(),
+            vec![
+                Token::Word("Boil"),
+                Token::Space(" \t "),
+                Token::Backstory("story")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_options_default_matches_parse() {
+        let input = "Boil {salt} // taste \\{braces}";
+        assert_eq!(
+            parse(input).unwrap(),
+            parse_with_options(input, &ParseOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_options_line_comments_disabled() {
+        let options = ParseOptions {
+            line_comments: false,
+            ..Default::default()
+        };
+        let tokens = parse_with_options("a // b", &options).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("a"),
+                Token::Space(" "),
+                Token::Word("//"),
+                Token::Space(" "),
+                Token::Word("b")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_options_escapes_disabled() {
+        let options = ParseOptions {
+            escapes: false,
+            ..Default::default()
+        };
+        let tokens = parse_with_options("\\{salt}", &options).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("\\"),
+                Token::Ingredient {
+                    name: "salt".into(),
+                    amounts: vec![]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_options_extra_symbols() {
+        let input = "{salt ~ pepper}(1 pinch)";
+        assert!(parse(input).is_err());
+
+        let options = ParseOptions {
+            extra_symbols: "~".into(),
+            ..Default::default()
+        };
+        let tokens = parse_with_options(input, &options).unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::Ingredient {
+                name: "salt ~ pepper".into(),
+                amounts: vec![Amount::new(Some("1"), Some("pinch"))]
+            }]
+        );
+    }
+
+    #[rstest]
+    #[case("{eggs}(2*3)", Amount::new(Some("2*3"), None), Quantity::new(6, 1))]
+    #[case(
+        "{milk}(1+1/2 cups)",
+        Amount::new(Some("1+1/2"), Some("cups")),
+        Quantity::new(3, 2)
+    )]
+    #[case(
+        "{milk}(1 + 1/2 cups)",
+        Amount::new(Some("1 + 1/2"), Some("cups")),
+        Quantity::new(3, 2)
+    )]
+    #[case(
+        "{flour}(2 * 100 gr)",
+        Amount::new(Some("2 * 100"), Some("gr")),
+        Quantity::new(200, 1)
+    )]
+    #[case(
+        "{salt}(1/2-1/4 tsp)",
+        Amount::new(Some("1/2-1/4"), Some("tsp")),
+        Quantity::new(1, 4)
+    )]
+    #[case("{eggs}(2-3)", Amount::new(Some("2"), Some("-3")), Quantity::new(2, 1))]
+    #[case("{eggs}(2+x)", Amount::new(Some("2"), Some("+x")), Quantity::new(2, 1))]
+    fn test_parse_options_arithmetic(
+        #[case] input: &str,
+        #[case] amount: Amount,
+        #[case] quantity: Option<Quantity>,
+    ) {
+        let options = ParseOptions {
+            arithmetic: true,
+            ..Default::default()
+        };
+        let tokens = parse_with_options(input, &options).unwrap();
+        let Token::Ingredient { amounts, .. } = &tokens[0] else {
+            panic!("not an ingredient: {tokens:?}");
+        };
+        assert_eq!(amounts[0], amount);
+        assert_eq!(amounts[0].quantity_value(), quantity);
+    }
+
+    #[test]
+    fn test_parse_options_arithmetic_disabled() {
+        let tokens = parse("{milk}(1+1/2 cups)").unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::Ingredient {
+                name: "milk".into(),
+                amounts: vec![
+                    Amount::new(Some("1"), Some("+1")),
+                    Amount::new(Some("2"), Some("cups"))
+                ]
+            }]
+        );
+    }
+
+    #[rstest]
+    #[case("m{pot}", vec![Token::Material("pot")])]
+    #[case("min{5 min}", vec![Token::Timer {
+                duration: "5 min",
+                kind: TimerKind::Active
+            }])]
+    #[case("{salt}", vec![Token::Ingredient { name: "salt".into(), amounts: vec![] }])]
+    #[case("&{pot}", vec![Token::Word("&{pot}")])]
+    #[case("t{5 min}", vec![Token::Word("t{5"), Token::Space(" "), Token::Word("min}")])]
+    #[case("milk", vec![Token::Word("milk")])]
+    #[case("m", vec![Token::Word("m")])]
+    #[case("warm{pot}", vec![Token::Word("warm{pot}")])]
+    #[case("mi{pot}", vec![Token::Word("mi{pot}")])]
+    #[case(
+        "{oil} m{pan} min{2 min}",
+        vec![
+            Token::Ingredient { name: "oil".into(), amounts: vec![] },
+            Token::Space(" "),
+            Token::Material("pan"),
+            Token::Space(" "),
+            Token::Timer {
+                duration: "2 min",
+                kind: TimerKind::Active
+            },
+        ]
+    )]
+    fn test_parse_options_prefixes(#[case] input: &str, #[case] expected: Vec<Token>) {
+        let options = ParseOptions {
+            material_prefix: "m".into(),
+            timer_prefix: "min".into(),
+            ..Default::default()
+        };
+        assert_eq!(parse_with_options(input, &options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_options_empty_prefixes() {
+        let options = ParseOptions {
+            material_prefix: "".into(),
+            timer_prefix: "".into(),
+            ..Default::default()
+        };
+        let tokens = parse_with_options("&{pot} t{5} {salt}", &options).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("&{pot}"),
+                Token::Space(" "),
+                Token::Word("t{5}"),
+                Token::Space(" "),
+                Token::Ingredient {
+                    name: "salt".into(),
+                    amounts: vec![]
+                },
+            ]
+        );
+    }
+
+    /// Small xorshift generator, so the random inputs are reproducible
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, max: usize) -> usize {
+            (self.next() % max as u64) as usize
+        }
+    }
+
+    fn assert_parse_returns(input: &str) {
+        for options in [
+            ParseOptions::default(),
+            ParseOptions {
+                line_comments: false,
+                escapes: false,
+                extra_symbols: "{}()".into(),
+                material_prefix: "m".into(),
+                timer_prefix: "".into(),
+                arithmetic: true,
+            },
+        ] {
+            if let Err(err) = parse_with_options(input, &options) {
+                assert!(
+                    err.offset() <= input.len(),
+                    "offset out of bounds: {input:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_never_panics_on_random_input() {
+        // Pieces of the syntax are more likely to reach the corner cases than plain bytes
+        let pieces = [
+            "{", "}", "(", ")", "&", "t", "@", "/", "\\", ">>", ">>>", ":", "---", "=", "*", "/*",
+            "*/", "//", " ", "\t", "\n", "\r\n", "1", "1/2", "1.5", "_", ",", "gr", "salt", "é",
+            "🍅",
+        ];
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        for _ in 0..5_000 {
+            let mut input = String::new();
+            for _ in 0..random.below(40) {
+                input.push_str(pieces[random.below(pieces.len())]);
+            }
+            assert_parse_returns(&input);
+        }
+        for _ in 0..1_000 {
+            let bytes: Vec<u8> = (0..random.below(64)).map(|_| random.next() as u8).collect();
+            assert_parse_returns(&String::from_utf8_lossy(&bytes));
+        }
+    }
+
+    #[rstest]
+    #[case("{".repeat(10_000))]
+    #[case("}".repeat(10_000))]
+    #[case(format!("{}salt{}", "{".repeat(1_000), "}".repeat(1_000)))]
+    #[case(" ".repeat(100_000))]
+    #[case("\n".repeat(100_000))]
+    #[case("=".repeat(100_000))]
+    #[case(format!("{{salt}}({})", "1".repeat(10_000)))]
+    #[case("a ".repeat(10_000))]
+    #[case(">> a: b\n".repeat(10_000))]
+    #[case("/*".repeat(10_000))]
+    fn test_parse_adversarial_input(#[case] input: String) {
+        assert_parse_returns(&input);
+    }
+
+    #[rstest]
+    #[case("Boil\n```\nafter", vec![Token::Word("Boil"), Token::Space("\n")], "```\nafter")]
+    #[case("Boil.\n---\nstory\n```", vec![Token::Word("Boil."), Token::Backstory("story\n")], "```")]
+    #[case("```", vec![], "```")]
+    #[case("Boil", vec![Token::Word("Boil")], "")]
+    #[case("Boil```", vec![Token::Word("Boil")], "```")]
+    fn test_parse_until(#[case] input: &str, #[case] tokens: Vec<Token>, #[case] rest: &str) {
+        let result = parse_until(input, |rest| rest.starts_with("```")).expect("failed to parse");
+        assert_eq!(result, (tokens, rest));
+    }
+
+    #[rstest]
+    #[case("Boil /* ``` */ it", vec![Token::Word("Boil"), Token::Space(" "), Token::Comment("```"), Token::Word("it")], "")]
+    #[case("Boil // ```\n```", vec![Token::Word("Boil"), Token::Space(" "), Token::Comment("```"), Token::Space("\n")], "```")]
+    #[case("See [```](https://a.b) ```", vec![Token::Word("See"), Token::Space(" "), Token::Link { text: "```", url: "https://a.b" }, Token::Space(" ")], "```")]
+    fn test_parse_until_stops_between_tokens(
+        #[case] input: &str,
+        #[case] tokens: Vec<Token>,
+        #[case] rest: &str,
+    ) {
+        let result = parse_until(input, |rest| rest.starts_with("```")).expect("failed to parse");
+        assert_eq!(result, (tokens, rest));
+    }
+
+    #[test]
+    fn test_parse_until_keeps_names_whole() {
+        let (tokens, rest) =
+            parse_until("Add {salt@sea} @end", |rest| rest.starts_with('@')).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("Add"),
+                Token::Space(" "),
+                Token::Ingredient {
+                    name: "salt@sea".into(),
+                    amounts: vec![]
+                },
+                Token::Space(" ")
+            ]
+        );
+        assert_eq!(rest, "@end");
+    }
+
+    #[test]
+    fn test_parse_until_with_options() {
+        let options = ParseOptions {
+            timer_prefix: "m".into(),
+            line_comments: false,
+            ..Default::default()
+        };
+        let (tokens, rest) =
+            parse_until_with_options("Wait m{5 min} //ok\n~~~\n{", &options, |rest| {
+                rest.starts_with("~~~")
+            })
+            .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("Wait"),
+                Token::Space(" "),
+                Token::Timer {
+                    duration: "5 min",
+                    kind: TimerKind::Active
+                },
+                Token::Space(" "),
+                Token::Word("//ok"),
+                Token::Space("\n")
+            ]
+        );
+        assert_eq!(rest, "~~~\n{");
+    }
+
+    #[test]
+    fn test_parse_until_error_offset_is_relative_to_input() {
+        for input in ["Boil {salt\n```", "Boil {salt ```", "Add {salt}(1\n```"] {
+            let err = parse_until(input, |rest| rest.starts_with("```")).unwrap_err();
+            assert_eq!(err.offset(), parse(input).unwrap_err().offset(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_spanned_slices_back_to_the_source() {
+        let input = ">> name: soup\nBoil {water}(1 l) in a &{pot} // big\n---\nstory";
+        let tokens = parse_spanned(input).expect("failed to parse");
+        let sources: Vec<&str> = tokens
+            .iter()
+            .map(|(_, span)| &input[span.clone()])
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                ">> name: soup\n",
+                "Boil",
+                " ",
+                "{water}(1 l)",
+                " ",
+                "in",
+                " ",
+                "a",
+                " ",
+                "&{pot}",
+                " ",
+                "// big",
+                "\n---\nstory",
+            ]
+        );
+        let plain: Vec<Token> = tokens.into_iter().map(|(token, _)| token).collect();
+        assert_eq!(plain, parse(input).unwrap());
+    }
+
+    #[test]
+    fn test_parse_spanned_partial_stops_at_the_error() {
+        let tokens = parse_spanned_partial("Boil {water", &DEFAULT_OPTIONS);
+        assert_eq!(
+            tokens,
+            vec![(Token::Word("Boil"), 0..4), (Token::Space(" "), 4..5)]
+        );
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn test_token_serialization_works() {
         let token = Token::Ingredient {
-            name: "quinoa",
-            quantity: Some("200"),
-            unit: Some("gr"),
+            name: "quinoa".into(),
+            amounts: vec![Amount::new(Some("200"), Some("gr"))],
         };
 
         let serialized = serde_json::to_string(&token).expect("failed to serialize");
@@ -650,15 +2165,14 @@ mod test {
     #[cfg(feature = "serde")]
     fn test_token_serialization_creates_right_payload() {
         let token = Token::Ingredient {
-            name: "quinoa",
-            quantity: Some("200"),
-            unit: Some("gr"),
+            name: "quinoa".into(),
+            amounts: vec![Amount::new(Some("200"), Some("gr"))],
         };
 
         let serialized = serde_json::to_string(&token).expect("failed to serialize");
         assert_eq!(
             serialized,
-            r#"{"token":"Ingredient","content":{"name":"quinoa","quantity":"200","unit":"gr"}}"#
+            r#"{"token":"Ingredient","content":{"name":"quinoa","amounts":[{"quantity":"200","unit":"gr"}]}}"#
         );
     }
 
@@ -0,0 +1,380 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Display;
+
+/// Symbol separating the integer part of a quantity from the fraction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DecimalMark {
+    /// `1.5`, with `1,200` for the thousands
+    #[default]
+    Point,
+    /// `1,5`, with `1.200` for the thousands
+    Comma,
+}
+
+/// Numeric value of an amount, kept as a fraction so `1/3` doesn't lose precision.
+///
+/// The fraction is always reduced to its lowest terms and the sign is
+/// carried by the numerator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Quantity {
+    numerator: i64,
+    denominator: i64,
+}
+
+impl Quantity {
+    /// Create a quantity from a fraction, returns `None` if the denominator is zero
+    /// or the reduced fraction doesn't fit
+    pub fn new(numerator: i64, denominator: i64) -> Option<Self> {
+        Self::from_wide(numerator.into(), denominator.into())
+    }
+
+    fn from_wide(numerator: i128, denominator: i128) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let divisor = gcd(numerator, denominator).max(1);
+        let sign = denominator.signum();
+        Some(Self {
+            numerator: (sign * numerator / divisor).try_into().ok()?,
+            denominator: (sign * denominator / divisor).try_into().ok()?,
+        })
+    }
+
+    /// Parse the quantity of an amount, like `3`, `1.5`, `3,5`, `1/2` or `1_000`
+    ///
+    /// The decimal mark is a point, see [`Quantity::parse_with_decimal_mark`].
+    /// Sums and products, like `1+1/2` or `2*3`, are computed.
+    ///
+    /// ```
+    /// use recipe_parser::Quantity;
+    ///
+    /// assert_eq!(Quantity::parse("1.5"), Quantity::new(3, 2));
+    /// assert_eq!(Quantity::parse("1,200"), Quantity::new(1200, 1));
+    /// assert_eq!(Quantity::parse("1+1/2"), Quantity::new(3, 2));
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::parse_with_decimal_mark(value, DecimalMark::Point)
+    }
+
+    /// Parse a quantity written with the given decimal mark.
+    ///
+    /// The other mark groups the thousands, like `1,200.5` or `1.200,5`,
+    /// when every group after the first one has 3 digits. Otherwise it's
+    /// read as a decimal mark too, so `1,5` is one and a half either way.
+    /// Underscores always group digits, like `1_200`.
+    ///
+    /// ```
+    /// use recipe_parser::{DecimalMark, Quantity};
+    ///
+    /// assert_eq!(Quantity::parse_with_decimal_mark("1,200", DecimalMark::Comma), Quantity::new(6, 5));
+    /// assert_eq!(Quantity::parse_with_decimal_mark("1.200", DecimalMark::Comma), Quantity::new(1200, 1));
+    /// ```
+    pub fn parse_with_decimal_mark(value: &str, mark: DecimalMark) -> Option<Self> {
+        let value = value.trim().replace('_', "");
+        if value.contains(['+', '-', '*']) {
+            return Self::evaluate(&value, mark);
+        }
+        Self::parse_fraction(&value, mark)
+    }
+
+    /// Sum of products of quantities, like `1+1/2` or `2 * 3`. The
+    /// products are computed first.
+    fn evaluate(value: &str, mark: DecimalMark) -> Option<Self> {
+        let mut total = Self::new(0, 1)?;
+        let mut sign = 1;
+        let mut rest = value;
+        loop {
+            let end = rest.find(['+', '-']).unwrap_or(rest.len());
+            let mut product = Self::new(sign, 1)?;
+            for factor in rest[..end].split('*') {
+                product = product.checked_mul(Self::parse_fraction(factor.trim(), mark)?)?;
+            }
+            total = total.checked_add(product)?;
+            let Some(operator) = rest[end..].chars().next() else {
+                return Some(total);
+            };
+            sign = if operator == '-' { -1 } else { 1 };
+            rest = &rest[end + 1..];
+        }
+    }
+
+    fn parse_fraction(value: &str, mark: DecimalMark) -> Option<Self> {
+        match value.split_once('/') {
+            Some((numerator, denominator)) => {
+                let numerator = Self::parse_decimal(numerator, mark)?;
+                let denominator = Self::parse_decimal(denominator, mark)?;
+                numerator.checked_div(denominator)
+            }
+            None => Self::parse_decimal(value, mark),
+        }
+    }
+
+    fn parse_decimal(value: &str, mark: DecimalMark) -> Option<Self> {
+        let (decimal, group) = match mark {
+            DecimalMark::Point => ('.', ','),
+            DecimalMark::Comma => (',', '.'),
+        };
+        let integer = value.split(decimal).next().unwrap_or_default();
+        let value = if is_grouped(integer, group) {
+            value.replacen(group, "", integer.matches(group).count())
+        } else {
+            value.replace(group, &String::from(decimal))
+        };
+        let value = value.as_str();
+        let (integer, fraction) = value.split_once(decimal).unwrap_or((value, ""));
+        let all_digits = |v: &str| v.chars().all(|c| c.is_ascii_digit());
+        if integer.is_empty() && fraction.is_empty()
+            || !all_digits(integer)
+            || !all_digits(fraction)
+        {
+            return None;
+        }
+        let denominator = 10_i64.checked_pow(fraction.len() as u32)?;
+        let integer: i64 = if integer.is_empty() {
+            0
+        } else {
+            integer.parse().ok()?
+        };
+        let fraction: i64 = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse().ok()?
+        };
+        let numerator = integer.checked_mul(denominator)?.checked_add(fraction)?;
+        Self::new(numerator, denominator)
+    }
+
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i64 {
+        self.denominator
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == 1
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// Add another quantity, returns `None` on overflow
+    pub fn checked_add(&self, other: Self) -> Option<Self> {
+        Self::from_wide(
+            i128::from(self.numerator) * i128::from(other.denominator)
+                + i128::from(other.numerator) * i128::from(self.denominator),
+            i128::from(self.denominator) * i128::from(other.denominator),
+        )
+    }
+
+    /// Multiply by another quantity, returns `None` on overflow
+    pub fn checked_mul(&self, other: Self) -> Option<Self> {
+        Self::from_wide(
+            i128::from(self.numerator) * i128::from(other.numerator),
+            i128::from(self.denominator) * i128::from(other.denominator),
+        )
+    }
+
+    /// Divide by another quantity, returns `None` when dividing by zero or on overflow
+    pub fn checked_div(&self, other: Self) -> Option<Self> {
+        Self::from_wide(
+            i128::from(self.numerator) * i128::from(other.denominator),
+            i128::from(self.denominator) * i128::from(other.numerator),
+        )
+    }
+
+    /// Render the quantity the way cooks read it, as a mixed number.
+    ///
+    /// ```
+    /// use recipe_parser::Quantity;
+    ///
+    /// let quantity = Quantity::new(3, 2).unwrap();
+    /// assert_eq!(quantity.to_mixed_string(), "1 1/2");
+    /// ```
+    pub fn to_mixed_string(&self) -> String {
+        let sign = if self.numerator < 0 { "-" } else { "" };
+        let numerator = self.numerator.unsigned_abs();
+        let denominator = self.denominator.unsigned_abs();
+        let whole = numerator / denominator;
+        let remainder = numerator % denominator;
+        match (whole, remainder) {
+            (whole, 0) => format!("{sign}{whole}"),
+            (0, remainder) => format!("{sign}{remainder}/{denominator}"),
+            (whole, remainder) => format!("{sign}{whole} {remainder}/{denominator}"),
+        }
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl Ord for Quantity {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        // The denominators are always positive
+        (i128::from(self.numerator) * i128::from(other.denominator))
+            .cmp(&(i128::from(other.numerator) * i128::from(self.denominator)))
+    }
+}
+
+impl PartialOrd for Quantity {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Digits grouped in thousands by `group`, like `1,200` or `12,345,678`
+fn is_grouped(integer: &str, group: char) -> bool {
+    let mut groups = integer.split(group);
+    let head = groups.next().unwrap_or_default();
+    let digits = |v: &str, len: core::ops::RangeInclusive<usize>| {
+        len.contains(&v.len()) && v.chars().all(|c| c.is_ascii_digit())
+    };
+    integer.contains(group) && digits(head, 1..=3) && groups.all(|v| digits(v, 3..=3))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("3", 3, 1)]
+    #[case("1.5", 3, 2)]
+    #[case("1,5", 3, 2)]
+    #[case(".2", 1, 5)]
+    #[case("1/2", 1, 2)]
+    #[case("2/4", 1, 2)]
+    #[case("1.5/3", 1, 2)]
+    #[case("3_000", 3000, 1)]
+    #[case("1_200", 1200, 1)]
+    #[case("1,200", 1200, 1)]
+    #[case("12,345,678", 12_345_678, 1)]
+    #[case("1,200.5", 2401, 2)]
+    #[case("1,20", 6, 5)]
+    #[case("1234,567", 1_234_567, 1000)]
+    #[case("1,200/2", 600, 1)]
+    #[case("2*3", 6, 1)]
+    #[case("1+1/2", 3, 2)]
+    #[case("1 + 1/2", 3, 2)]
+    #[case("2-1/4", 7, 4)]
+    #[case("1+2*3", 7, 1)]
+    #[case("2*3-1*2", 4, 1)]
+    #[case("1/2*1/2", 1, 4)]
+    #[case("1-2", -1, 1)]
+    fn test_quantity_parse_ok(
+        #[case] input: &str,
+        #[case] numerator: i64,
+        #[case] denominator: i64,
+    ) {
+        assert_eq!(
+            Quantity::parse(input),
+            Quantity::new(numerator, denominator)
+        );
+    }
+
+    #[rstest]
+    #[case("1,5", 3, 2)]
+    #[case("1,200", 6, 5)]
+    #[case("1.200", 1200, 1)]
+    #[case("1.200.000,25", 4_800_001, 4)]
+    #[case("1_200,5", 2401, 2)]
+    #[case("1.5", 3, 2)]
+    fn test_quantity_parse_with_decimal_comma(
+        #[case] input: &str,
+        #[case] numerator: i64,
+        #[case] denominator: i64,
+    ) {
+        assert_eq!(
+            Quantity::parse_with_decimal_mark(input, DecimalMark::Comma),
+            Quantity::new(numerator, denominator)
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("abc")]
+    #[case("1,200,5")]
+    #[case("1,2.3")]
+    #[case("1/0")]
+    #[case("-1")]
+    #[case("1+")]
+    #[case("2**3")]
+    #[case("1+a")]
+    #[case("1/0+1")]
+    #[case("1.2.3")]
+    #[case("99999999999999999999")]
+    fn test_quantity_parse_invalid(#[case] input: &str) {
+        assert_eq!(Quantity::parse(input), None);
+    }
+
+    #[rstest]
+    #[case(3, 2, "1 1/2")]
+    #[case(5, 4, "1 1/4")]
+    #[case(2, 4, "1/2")]
+    #[case(4, 4, "1")]
+    #[case(0, 3, "0")]
+    #[case(6, 1, "6")]
+    #[case(-3, 2, "-1 1/2")]
+    #[case(3, -4, "-3/4")]
+    #[case(i64::MIN, 1, "-9223372036854775808")]
+    #[case(i64::MIN + 1, -1, "9223372036854775807")]
+    fn test_quantity_to_mixed_string(
+        #[case] numerator: i64,
+        #[case] denominator: i64,
+        #[case] expected: &str,
+    ) {
+        let quantity = Quantity::new(numerator, denominator).unwrap();
+        assert_eq!(quantity.to_mixed_string(), expected);
+    }
+
+    #[test]
+    fn test_quantity_arithmetic() {
+        let half = Quantity::new(1, 2).unwrap();
+        let third = Quantity::new(1, 3).unwrap();
+        assert_eq!(half.checked_add(third), Quantity::new(5, 6));
+        assert_eq!(half.checked_mul(third), Quantity::new(1, 6));
+        assert_eq!(half.checked_div(third), Quantity::new(3, 2));
+        let max = Quantity::new(i64::MAX, 1).unwrap();
+        assert_eq!(max.checked_add(max), None);
+    }
+
+    #[test]
+    fn test_quantity_ordering() {
+        let half = Quantity::new(1, 2).unwrap();
+        let third = Quantity::new(1, 3).unwrap();
+        let minus_one = Quantity::new(1, -1).unwrap();
+        assert!(third < half);
+        assert!(minus_one < third);
+        assert_eq!(
+            half.cmp(&Quantity::new(2, 4).unwrap()),
+            core::cmp::Ordering::Equal
+        );
+        assert!(Quantity::new(i64::MAX, 1).unwrap() > Quantity::new(i64::MAX - 1, 1).unwrap());
+    }
+
+    #[test]
+    fn test_quantity_new_invalid() {
+        assert_eq!(Quantity::new(1, 0), None);
+        assert_eq!(Quantity::new(i64::MIN, -1), None);
+    }
+}
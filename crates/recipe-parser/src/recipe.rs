@@ -0,0 +1,1459 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::aggregate::normalize_name;
+use crate::{
+    Amount, Lint, Quantity, RecipeError, RenderOptions, TextSpan, TimerKind, Token, aggregate,
+    parse_duration, parse_spanned, render_text, strip_backstory, text_spans,
+};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Ingredient<'a> {
+    pub name: String,
+    pub amounts: Vec<Amount<'a>>,
+    /// Comment next to the ingredient, only filled when
+    /// [`RecipeOptions::comment_notes`] is enabled
+    pub note: Option<String>,
+    /// Byte range of the ingredient in the source, from the `{` to the
+    /// `)` of the amounts. Only known when the recipe was built from
+    /// source, not from bare tokens.
+    pub span: Option<Range<usize>>,
+}
+
+pub type RecipeRef<'a> = Ingredient<'a>;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Timer {
+    /// The text of the timer, like `25 minutes` or the name `rest`
+    pub duration: String,
+    /// The duration of the timer. A name, like `t{rest}`, is looked up in the
+    /// metadata, like `>> rest: 30 minutes`, before parsing the text itself.
+    /// `None` when it cannot be understood.
+    pub resolved: Option<Duration>,
+    /// Whether the cook is busy during the timer, like `t{30 min}!passive`
+    pub kind: TimerKind,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Material {
+    pub name: String,
+}
+
+/// Options to customize how the tokens are grouped into a [`Recipe`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecipeOptions {
+    /// Attach a comment as a note to the ingredient that follows it, like
+    /// `/* organic if possible */ {tomatoes}(3)`, or else to the one before
+    /// it on the same line, like `{tomatoes}(3) // organic`
+    pub comment_notes: bool,
+    /// Leave the backstory out, for a view with just the recipe
+    pub strip_backstory: bool,
+}
+
+/// Structured view of a recipe, with its components grouped
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Recipe<'a> {
+    pub name: Option<String>,
+    pub metadata: HashMap<String, String>,
+    pub ingredients: Vec<Ingredient<'a>>,
+    pub recipes_refs: Vec<RecipeRef<'a>>,
+    pub timers: Vec<Timer>,
+    pub materials: Vec<Material>,
+    pub backstory: Option<String>,
+    pub instructions: Vec<Token<'a>>,
+}
+
+/// How many people a recipe serves, from the `>> servings:` metadata
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Servings {
+    Exact(u32),
+    /// From the first to the second, like `4-6`
+    Range(u32, u32),
+}
+
+impl Servings {
+    /// Parse the leading number, or range of numbers, of a servings value.
+    /// The words after it are ignored.
+    ///
+    /// ```
+    /// use recipe_parser::Servings;
+    ///
+    /// assert_eq!(Servings::parse("4 people"), Some(Servings::Exact(4)));
+    /// assert_eq!(Servings::parse("4-6"), Some(Servings::Range(4, 6)));
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        let (from, rest) = leading_number(value.trim_start())?;
+        let rest = rest.trim_start();
+        let to = ["-", "–", "to "]
+            .iter()
+            .find_map(|separator| rest.strip_prefix(separator))
+            .and_then(|rest| leading_number(rest.trim_start()));
+        match to {
+            Some((to, _)) if to == from => Some(Self::Exact(from)),
+            Some((to, _)) if to > from => Some(Self::Range(from, to)),
+            Some(_) => None,
+            None => Some(Self::Exact(from)),
+        }
+    }
+
+    /// The smallest amount of people served
+    pub fn min(&self) -> u32 {
+        match self {
+            Self::Exact(servings) | Self::Range(servings, _) => *servings,
+        }
+    }
+
+    /// The largest amount of people served
+    pub fn max(&self) -> u32 {
+        match self {
+            Self::Exact(servings) | Self::Range(_, servings) => *servings,
+        }
+    }
+}
+
+impl Display for Servings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(servings) => write!(f, "{servings}"),
+            Self::Range(from, to) => write!(f, "{from}-{to}"),
+        }
+    }
+}
+
+fn leading_number(value: &str) -> Option<(u32, &str)> {
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    Some((value[..end].parse().ok()?, &value[end..]))
+}
+
+/// Machine readable summary of a recipe, e.g: for a recipe card or a search index
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RecipeSummary {
+    pub title: Option<String>,
+    pub servings: Option<Servings>,
+    /// Sum of all the timers that could be understood
+    pub total_time_seconds: u64,
+    pub ingredient_count: usize,
+    pub material_count: usize,
+    /// Names of the ingredients, without repetitions
+    pub ingredients: Vec<String>,
+}
+
+/// Metadata keys of the nutrition facts, in the order they're listed
+const NUTRITION_KEYS: &[&str] = &[
+    "calories",
+    "fat",
+    "saturated fat",
+    "carbohydrates",
+    "sugar",
+    "fiber",
+    "protein",
+    "salt",
+    "sodium",
+];
+
+/// Nutrition facts of a recipe, from metadata like `>> calories: 2000 kcal`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Nutrition {
+    /// In the order of the known keys: calories, fat, saturated fat,
+    /// carbohydrates, sugar, fiber, protein, salt and sodium
+    pub facts: Vec<NutritionFact>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NutritionFact {
+    pub name: String,
+    pub quantity: Quantity,
+    /// The text after the quantity, like `kcal` or `g`
+    pub unit: Option<String>,
+}
+
+impl Nutrition {
+    /// The fact of the given name, ignoring case and surrounding spaces
+    pub fn get(&self, name: &str) -> Option<&NutritionFact> {
+        let name = normalize_name(name);
+        self.facts.iter().find(|fact| fact.name == name)
+    }
+}
+
+/// Amount of an ingredient computed from a percentage of another one,
+/// see [`Recipe::resolve_percentages`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ResolvedAmount {
+    /// Position of the ingredient in [`Recipe::ingredients`]
+    pub index: usize,
+    pub quantity: Quantity,
+    /// The unit of the other ingredient
+    pub unit: Option<String>,
+}
+
+/// The steps of a recipe and how they depend on each other, e.g: to show
+/// what can be prepared in parallel
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FlowGraph {
+    /// One node per step, in the order of [`Recipe::steps`]
+    pub nodes: Vec<FlowNode>,
+    /// `(from, to)` indexes of the nodes, `to` needs `from` to be done first.
+    /// Sorted and without repetitions.
+    pub edges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FlowNode {
+    pub step: String,
+    /// Names of the ingredients used in the step, without repetitions
+    pub ingredients: Vec<String>,
+    /// Names of the materials used in the step, without repetitions
+    pub materials: Vec<String>,
+}
+
+impl FlowGraph {
+    /// Indexes of the steps that must be done before the given one
+    pub fn dependencies(&self, node: usize) -> Vec<usize> {
+        self.edges
+            .iter()
+            .filter(|(_, to)| *to == node)
+            .map(|(from, _)| *from)
+            .collect()
+    }
+}
+
+impl<'a> Recipe<'a> {
+    /// Group the tokens into a structured recipe
+    pub fn from_tokens(tokens: Vec<Token<'a>>) -> Self {
+        Self::from_tokens_with_options(tokens, &RecipeOptions::default())
+    }
+
+    /// Group the tokens into a structured recipe, using the given options
+    ///
+    /// ```
+    /// use recipe_parser::{parse, Recipe, RecipeOptions};
+    ///
+    /// let tokens = parse("Add /* organic if possible */ {tomatoes}(3)").unwrap();
+    /// let options = RecipeOptions {
+    ///     comment_notes: true,
+    ///     ..Default::default()
+    /// };
+    /// let recipe = Recipe::from_tokens_with_options(tokens, &options);
+    /// assert_eq!(recipe.ingredients[0].note.as_deref(), Some("organic if possible"));
+    /// ```
+    pub fn from_tokens_with_options(tokens: Vec<Token<'a>>, options: &RecipeOptions) -> Self {
+        let tokens = if options.strip_backstory {
+            strip_backstory(&tokens)
+        } else {
+            tokens
+        };
+        let mut metadata = HashMap::new();
+
+        let mut ingredients = Vec::new();
+        let mut recipes_refs = Vec::new();
+        let mut timers = Vec::new();
+        let mut materials = Vec::new();
+        let mut backstory = String::new();
+        // Position of each ingredient in the tokens
+        let mut ingredient_positions = Vec::new();
+
+        for (position, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Metadata { key, value } => {
+                    metadata.insert(key.to_string(), value.to_string());
+                }
+                Token::Ingredient { name, amounts } => {
+                    let i = Ingredient {
+                        name: name.to_string(),
+                        amounts: amounts.clone(),
+                        note: None,
+                        span: None,
+                    };
+                    ingredients.push(i);
+                    ingredient_positions.push(position);
+                }
+                Token::RecipeRef { name, amounts } => {
+                    let i = RecipeRef {
+                        name: name.to_string(),
+                        amounts: amounts.clone(),
+                        note: None,
+                        span: None,
+                    };
+                    recipes_refs.push(i);
+                }
+                Token::Timer { duration, kind } => timers.push(Timer {
+                    duration: duration.to_string(),
+                    resolved: None,
+                    kind: *kind,
+                }),
+                Token::Material(material) => materials.push(Material {
+                    name: material.to_string(),
+                }),
+                Token::Backstory(bs) => backstory.push_str(bs),
+                _ => {}
+            };
+        }
+        if options.comment_notes {
+            for (position, token) in tokens.iter().enumerate() {
+                let Token::Comment(comment) = token else {
+                    continue;
+                };
+                let Some(position) = note_target(&tokens, position) else {
+                    continue;
+                };
+                let Some(index) = ingredient_positions.binary_search(&position).ok() else {
+                    continue;
+                };
+                let note = ingredients[index].note.get_or_insert_with(String::new);
+                if !note.is_empty() {
+                    note.push(' ');
+                }
+                note.push_str(comment.trim());
+            }
+        }
+        for timer in &mut timers {
+            timer.resolved = resolve_timer(&metadata, &timer.duration);
+        }
+        let name = metadata.get("name").cloned();
+        Self {
+            name,
+            ingredients,
+            timers,
+            materials,
+            metadata,
+            recipes_refs,
+            backstory: {
+                if backstory.is_empty() {
+                    None
+                } else {
+                    Some(backstory)
+                }
+            },
+            instructions: tokens,
+        }
+    }
+
+    /// Group the tokens with their spans, from [`parse_spanned`](crate::parse_spanned),
+    /// into a structured recipe. Unlike [`from_tokens`](Self::from_tokens)
+    /// the ingredients know where they are in the source.
+    ///
+    /// ```
+    /// use recipe_parser::{parse_spanned, Recipe, RecipeOptions};
+    ///
+    /// let source = "Mix the {flour}(500 gr) with {water}";
+    /// let tokens = parse_spanned(source).unwrap();
+    /// let recipe = Recipe::from_spanned_tokens(tokens, &RecipeOptions::default());
+    /// let span = recipe.ingredients[0].span.clone().unwrap();
+    /// assert_eq!(&source[span], "{flour}(500 gr)");
+    /// ```
+    pub fn from_spanned_tokens(
+        tokens: Vec<(Token<'a>, Range<usize>)>,
+        options: &RecipeOptions,
+    ) -> Self {
+        let (tokens, spans): (Vec<Token<'a>>, Vec<Range<usize>>) = tokens.into_iter().unzip();
+        let mut ingredient_spans = vec![];
+        let mut ref_spans = vec![];
+        for (token, span) in tokens.iter().zip(spans) {
+            match token {
+                Token::Ingredient { .. } => ingredient_spans.push(span),
+                Token::RecipeRef { .. } => ref_spans.push(span),
+                _ => {}
+            }
+        }
+        let mut recipe = Self::from_tokens_with_options(tokens, options);
+        for (ingredient, span) in recipe.ingredients.iter_mut().zip(ingredient_spans) {
+            ingredient.span = Some(span);
+        }
+        for (recipe_ref, span) in recipe.recipes_refs.iter_mut().zip(ref_spans) {
+            recipe_ref.span = Some(span);
+        }
+        recipe
+    }
+
+    /// Title to display for the recipe, in order of precedence:
+    ///
+    /// 1. the `>> name:` metadata
+    /// 2. the `>> title:` metadata
+    /// 3. the first section heading, like `= Pancakes`
+    ///
+    /// ```
+    /// use recipe_parser::Recipe;
+    ///
+    /// let recipe = Recipe::try_from("= Pancakes\nMix the {flour}").unwrap();
+    /// assert_eq!(recipe.title(), Some("Pancakes"));
+    /// ```
+    pub fn title(&self) -> Option<&str> {
+        self.metadata
+            .get("name")
+            .or_else(|| self.metadata.get("title"))
+            .map(|title| title.as_str())
+            .or_else(|| {
+                self.instructions.iter().find_map(|token| match token {
+                    Token::Section(section) => Some(*section),
+                    _ => None,
+                })
+            })
+    }
+
+    /// Names of the recipes referenced with `@{}` or `@recipe{}`, each once
+    /// in the order they're first used
+    ///
+    /// ```
+    /// use recipe_parser::Recipe;
+    ///
+    /// let recipe = Recipe::try_from("Make the @recipe{dough}, top with @{sauce} and @{dough}").unwrap();
+    /// assert_eq!(recipe.recipe_refs(), vec!["dough", "sauce"]);
+    /// ```
+    pub fn recipe_refs(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = vec![];
+        for recipe_ref in &self.recipes_refs {
+            if !names.contains(&recipe_ref.name.as_str()) {
+                names.push(&recipe_ref.name);
+            }
+        }
+        names
+    }
+
+    /// Text of each step, a step is a non empty line of the instructions.
+    ///
+    /// Sections and the backstory are not steps.
+    pub fn steps(&self) -> Vec<String> {
+        self.step_tokens()
+            .into_iter()
+            .map(|(step, _)| step)
+            .collect()
+    }
+
+    /// Each step as plain text and the tokens in it, like the ingredients,
+    /// see [`text_spans`]. The spaces around the step are left out.
+    ///
+    /// ```
+    /// use recipe_parser::{Recipe, TextSpan, TimerKind, Token};
+    ///
+    /// let recipe = Recipe::try_from("Boil for t{5 min}.\n  Serve hot  ").unwrap();
+    /// let steps = recipe.step_spans();
+    /// let timer = Token::Timer { duration: "5 min", kind: TimerKind::Active };
+    /// assert_eq!(steps[0][1], TextSpan::Token(timer));
+    /// assert_eq!(steps[1], vec![TextSpan::Text("Serve hot".to_string())]);
+    /// ```
+    pub fn step_spans(&self) -> Vec<Vec<TextSpan<'a>>> {
+        self.step_tokens()
+            .into_iter()
+            .map(|(_, tokens)| {
+                let tokens: Vec<Token<'a>> = tokens.into_iter().cloned().collect();
+                let mut spans = text_spans(&tokens);
+                if let Some(TextSpan::Text(text)) = spans.first_mut() {
+                    *text = text.trim_start().to_string();
+                }
+                if let Some(TextSpan::Text(text)) = spans.last_mut() {
+                    text.truncate(text.trim_end().len());
+                }
+                spans.retain(|span| !matches!(span, TextSpan::Text(text) if text.is_empty()));
+                spans
+            })
+            .collect()
+    }
+
+    /// Text of each step together with its tokens
+    fn step_tokens(&self) -> Vec<(String, Vec<&Token<'a>>)> {
+        let mut lines: Vec<Vec<&Token<'a>>> = vec![vec![]];
+        for token in &self.instructions {
+            match token {
+                Token::Section(_) | Token::Backstory(_) => {}
+                Token::Space(space) if space.contains('\n') => lines.push(vec![]),
+                token => lines.last_mut().expect("there's always a line").push(token),
+            }
+        }
+        let options = RenderOptions {
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        lines
+            .into_iter()
+            .filter_map(|tokens| {
+                let line: Vec<Token> = tokens.iter().map(|token| (*token).clone()).collect();
+                let step = render_text(&line, &options).trim().to_string();
+                (!step.is_empty()).then_some((step, tokens))
+            })
+            .collect()
+    }
+
+    /// The steps as a graph. A step depends on the last previous one that
+    /// used any of its ingredients or materials, with names matched ignoring
+    /// case and surrounding spaces.
+    ///
+    /// ```
+    /// use recipe_parser::Recipe;
+    ///
+    /// let recipe = Recipe::try_from(
+    ///     "Boil the {potatoes}(3) in a &{pot}.\nChop the {onion}.\nMash the {potatoes} with the {onion}.",
+    /// )
+    /// .unwrap();
+    /// let graph = recipe.flow_graph();
+    /// assert_eq!(graph.nodes.len(), 3);
+    /// assert_eq!(graph.edges, vec![(0, 2), (1, 2)]);
+    /// ```
+    pub fn flow_graph(&self) -> FlowGraph {
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        // Last step that used each ingredient or material
+        let mut last_use: HashMap<(bool, String), usize> = HashMap::new();
+        for (index, (step, tokens)) in self.step_tokens().into_iter().enumerate() {
+            let mut ingredients: Vec<String> = vec![];
+            let mut materials: Vec<String> = vec![];
+            for token in tokens {
+                let (is_material, name, names) = match token {
+                    Token::Ingredient { name, .. } => (false, &**name, &mut ingredients),
+                    Token::Material(name) => (true, *name, &mut materials),
+                    _ => continue,
+                };
+                let key = (is_material, normalize_name(name));
+                if let Some(previous) = last_use.insert(key.clone(), index)
+                    && previous != index
+                {
+                    edges.push((previous, index));
+                }
+                if !names.iter().any(|n| normalize_name(n) == key.1) {
+                    names.push(name.trim().to_string());
+                }
+            }
+            nodes.push(FlowNode {
+                step,
+                ingredients,
+                materials,
+            });
+        }
+        edges.sort();
+        edges.dedup();
+        FlowGraph { nodes, edges }
+    }
+
+    /// Ingredients grouped by the section heading they appear under, the
+    /// ones before any heading are under `None`. Sections without
+    /// ingredients are left out.
+    ///
+    /// An ingredient mentioned again without amounts, like "add the rest of
+    /// the {flour}", refers to an earlier one and is only listed where it
+    /// was first used.
+    ///
+    /// ```
+    /// use recipe_parser::Recipe;
+    ///
+    /// let recipe = Recipe::try_from(
+    ///     "= Dough\nMix the {flour}(200 gr).\n= Filling\nCook the {apples}(3), add the {flour}.",
+    /// )
+    /// .unwrap();
+    /// let sections = recipe.ingredients_by_section();
+    /// assert_eq!(sections[0].0.as_deref(), Some("Dough"));
+    /// assert_eq!(sections[1].1[0].name, "apples");
+    /// assert_eq!(sections[1].1.len(), 1);
+    /// ```
+    pub fn ingredients_by_section(&self) -> Vec<(Option<String>, Vec<Ingredient<'a>>)> {
+        let mut sections: Vec<(Option<String>, Vec<Ingredient<'a>>)> = vec![(None, vec![])];
+        let mut seen: Vec<String> = vec![];
+        let mut ingredients = self.ingredients.iter();
+        for token in &self.instructions {
+            match token {
+                Token::Section(section) => sections.push((Some(section.to_string()), vec![])),
+                Token::Ingredient { .. } => {
+                    let Some(ingredient) = ingredients.next() else {
+                        break;
+                    };
+                    let name = normalize_name(&ingredient.name);
+                    if ingredient.amounts.is_empty() && seen.contains(&name) {
+                        continue;
+                    }
+                    seen.push(name);
+                    if let Some((_, current)) = sections.last_mut() {
+                        current.push(ingredient.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        sections.retain(|(_, ingredients)| !ingredients.is_empty());
+        sections
+    }
+
+    /// Rename every mention of an ingredient, in the ingredients, in the
+    /// steps and in percentages of it like `(2% butter)`. Names are matched
+    /// ignoring case and surrounding spaces.
+    ///
+    /// ```
+    /// use recipe_parser::Recipe;
+    ///
+    /// let mut recipe = Recipe::try_from("Melt the {Butter}(50 gr), then add more {butter}").unwrap();
+    /// recipe.rename_ingredient("butter", String::from("margarine"));
+    /// assert_eq!(recipe.ingredients[0].name, "margarine");
+    /// assert_eq!(recipe.steps(), vec!["Melt the margarine, then add more margarine"]);
+    /// ```
+    pub fn rename_ingredient(&mut self, from: &str, to: impl Into<Cow<'a, str>>) {
+        let from = normalize_name(from);
+        let to = to.into();
+        let rename_amounts = |amounts: &mut Vec<Amount<'a>>| {
+            for amount in amounts {
+                if amount
+                    .percentage()
+                    .is_some_and(|percentage| normalize_name(percentage.of) == from)
+                {
+                    amount.unit = Some(Cow::Owned(format!("% {}", to)));
+                }
+            }
+        };
+        for ingredient in &mut self.ingredients {
+            if normalize_name(&ingredient.name) == from {
+                ingredient.name = to.to_string();
+            }
+            rename_amounts(&mut ingredient.amounts);
+        }
+        for token in &mut self.instructions {
+            if let Token::Ingredient { name, amounts } = token {
+                if normalize_name(name) == from {
+                    *name = to.clone();
+                }
+                rename_amounts(amounts);
+            }
+        }
+    }
+
+    /// Combine two recipes into one, e.g: a main and a side for a meal.
+    ///
+    /// The steps of each recipe go under a section named after its
+    /// [`title`](Self::title), unless it already starts with it. The
+    /// metadata of both is kept, when a key is in both the value of `self`
+    /// wins, and so does its name. The backstories are joined one after the
+    /// other.
+    ///
+    /// ```
+    /// use recipe_parser::Recipe;
+    ///
+    /// let main = Recipe::try_from(">> name: Steak\n>> servings: 2\nGrill the {steak}").unwrap();
+    /// let side = Recipe::try_from(">> name: Salad\n>> servings: 4\nToss the {lettuce}").unwrap();
+    /// let meal = main.merge(&side);
+    /// assert_eq!(meal.steps(), vec!["Grill the steak", "Toss the lettuce"]);
+    /// assert_eq!(meal.ingredients_by_section()[1].0.as_deref(), Some("Salad"));
+    /// assert_eq!(meal.metadata["servings"], "2");
+    /// ```
+    pub fn merge<'b>(&'b self, other: &'b Recipe<'a>) -> Recipe<'b> {
+        let mut metadata = other.metadata.clone();
+        metadata.extend(self.metadata.clone());
+        let mut instructions = merged_instructions(self);
+        let side = merged_instructions(other);
+        if !instructions.is_empty() && !side.is_empty() {
+            instructions.push(Token::Space("\n\n"));
+        }
+        instructions.extend(side);
+        let backstory: Vec<&str> = [&self.backstory, &other.backstory]
+            .into_iter()
+            .flatten()
+            .map(|backstory| backstory.trim())
+            .collect();
+        Recipe {
+            name: metadata.get("name").cloned(),
+            metadata,
+            ingredients: [self.ingredients.clone(), other.ingredients.clone()].concat(),
+            recipes_refs: [self.recipes_refs.clone(), other.recipes_refs.clone()].concat(),
+            timers: [self.timers.clone(), other.timers.clone()].concat(),
+            materials: [self.materials.clone(), other.materials.clone()].concat(),
+            backstory: (!backstory.is_empty()).then(|| backstory.join("\n\n")),
+            instructions,
+        }
+    }
+
+    /// Compute the amounts written as a percentage of another ingredient,
+    /// like `{salt}(2% flour)`, from the total of that ingredient.
+    ///
+    /// The amounts that cannot be computed are left as they are and reported
+    /// as lints: when the other ingredient is missing, has no numeric
+    /// amount, or is in more than one unit.
+    ///
+    /// ```
+    /// use recipe_parser::{Quantity, Recipe};
+    ///
+    /// let recipe = Recipe::try_from("Mix the {flour}(500 gr) with the {salt}(2% flour)").unwrap();
+    /// let (amounts, lints) = recipe.resolve_percentages();
+    /// assert_eq!(amounts[0].index, 1);
+    /// assert_eq!(amounts[0].quantity, Quantity::new(10, 1).unwrap());
+    /// assert_eq!(amounts[0].unit.as_deref(), Some("gr"));
+    /// assert!(lints.is_empty());
+    /// ```
+    pub fn resolve_percentages(&self) -> (Vec<ResolvedAmount>, Vec<Lint>) {
+        let totals = aggregate(&self.instructions, None);
+        let mut amounts = vec![];
+        let mut lints = vec![];
+        for (index, ingredient) in self.ingredients.iter().enumerate() {
+            let Some(percentage) = ingredient.amounts.first().and_then(Amount::percentage) else {
+                continue;
+            };
+            let of = normalize_name(percentage.of);
+            let other = totals
+                .iter()
+                .find(|total| normalize_name(&total.name) == of);
+            let resolved = match other.map(|other| other.totals.as_slice()) {
+                None => Err("is not in the recipe"),
+                Some([total]) if total.unit.as_deref().is_some_and(|u| u.starts_with('%')) => {
+                    Err("is a percentage too")
+                }
+                Some([total]) => total
+                    .quantity
+                    .and_then(|quantity| {
+                        let factor = percentage.percent.checked_div(Quantity::new(100, 1)?)?;
+                        quantity.checked_mul(factor)
+                    })
+                    .map(|quantity| (quantity, total.unit.clone()))
+                    .ok_or("has no numeric amount"),
+                Some([]) => Err("has no numeric amount"),
+                Some(_) => Err("has amounts in more than one unit"),
+            };
+            match resolved {
+                Ok((quantity, unit)) => amounts.push(ResolvedAmount {
+                    index,
+                    quantity,
+                    unit,
+                }),
+                Err(reason) => lints.push(Lint {
+                    message: format!(
+                        "the amount of `{}` cannot be computed, `{}` {reason}",
+                        ingredient.name.trim(),
+                        percentage.of
+                    ),
+                    range: None,
+                }),
+            }
+        }
+        (amounts, lints)
+    }
+
+    /// The `>> servings:` metadata, if it starts with a number or a range
+    pub fn servings(&self) -> Option<Servings> {
+        Servings::parse(self.metadata.get("servings")?)
+    }
+
+    /// Nutrition facts of the whole recipe, from the metadata with a known
+    /// key and a value starting with a number, like `>> protein: 80 g`.
+    /// `None` when there are none.
+    pub fn nutrition(&self) -> Option<Nutrition> {
+        let facts: Vec<NutritionFact> = NUTRITION_KEYS
+            .iter()
+            .filter_map(|name| {
+                let (_, value) = self
+                    .metadata
+                    .iter()
+                    .find(|(key, _)| normalize_name(key) == *name)?;
+                let value = value.trim();
+                let end = value
+                    .find(|c: char| !(c.is_ascii_digit() || ".,/_".contains(c)))
+                    .unwrap_or(value.len());
+                let unit = value[end..].trim();
+                Some(NutritionFact {
+                    name: name.to_string(),
+                    quantity: Quantity::parse(&value[..end])?,
+                    unit: (!unit.is_empty()).then(|| unit.to_string()),
+                })
+            })
+            .collect();
+        (!facts.is_empty()).then_some(Nutrition { facts })
+    }
+
+    /// Nutrition facts of a single serving, the [`nutrition`](Self::nutrition)
+    /// divided by the [`servings`](Self::servings). When the servings are a
+    /// range, like `4-6`, it's divided by the middle of it.
+    ///
+    /// `None` when the nutrition or the servings are missing.
+    ///
+    /// ```
+    /// use recipe_parser::{Quantity, Recipe};
+    ///
+    /// let recipe = Recipe::try_from(">> servings: 4\n>> calories: 2000 kcal\nMix").unwrap();
+    /// let calories = recipe.nutrition_per_serving().unwrap().facts[0].clone();
+    /// assert_eq!(calories.quantity, Quantity::new(500, 1).unwrap());
+    /// assert_eq!(calories.unit.as_deref(), Some("kcal"));
+    /// ```
+    pub fn nutrition_per_serving(&self) -> Option<Nutrition> {
+        let servings = self.servings()?;
+        let servings = Quantity::new(i64::from(servings.min()) + i64::from(servings.max()), 2)?;
+        let mut nutrition = self.nutrition()?;
+        for fact in &mut nutrition.facts {
+            fact.quantity = fact.quantity.checked_div(servings)?;
+        }
+        Some(nutrition)
+    }
+
+    /// Sum of the durations of all the timers, the ones that cannot be
+    /// understood are skipped
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use recipe_parser::Recipe;
+    ///
+    /// let recipe = Recipe::try_from(">> rest: 30 minutes\nRest t{rest}, knead and rest t{rest}").unwrap();
+    /// assert_eq!(recipe.total_time(), Duration::from_secs(60 * 60));
+    /// ```
+    pub fn total_time(&self) -> Duration {
+        self.timers.iter().filter_map(|timer| timer.resolved).sum()
+    }
+
+    /// Sum of the durations of the timers the cook is busy during, the
+    /// timers not marked `!passive`
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use recipe_parser::Recipe;
+    ///
+    /// let recipe = Recipe::try_from("Knead t{10 min}, rest t{1 h}!passive and bake t{30 min}!passive").unwrap();
+    /// assert_eq!(recipe.active_time(), Duration::from_secs(10 * 60));
+    /// assert_eq!(recipe.passive_time(), Duration::from_secs(90 * 60));
+    /// ```
+    pub fn active_time(&self) -> Duration {
+        self.time_of(TimerKind::Active)
+    }
+
+    /// Sum of the durations of the timers marked `!passive`, like resting
+    /// or baking
+    pub fn passive_time(&self) -> Duration {
+        self.time_of(TimerKind::Passive)
+    }
+
+    fn time_of(&self, kind: TimerKind) -> Duration {
+        self.timers
+            .iter()
+            .filter(|timer| timer.kind == kind)
+            .filter_map(|timer| timer.resolved)
+            .sum()
+    }
+
+    /// Summarize the recipe, ingredients and materials are counted once
+    /// even if they appear multiple times.
+    pub fn summary(&self) -> RecipeSummary {
+        let mut ingredients: Vec<String> = vec![];
+        for ingredient in &self.ingredients {
+            let name = ingredient.name.trim();
+            if !ingredients
+                .iter()
+                .any(|i| normalize_name(i) == normalize_name(name))
+            {
+                ingredients.push(name.to_string());
+            }
+        }
+        let mut materials: Vec<String> = self
+            .materials
+            .iter()
+            .map(|m| normalize_name(&m.name))
+            .collect();
+        materials.sort();
+        materials.dedup();
+
+        RecipeSummary {
+            title: self.title().map(|title| title.to_string()),
+            servings: self.servings(),
+            total_time_seconds: self.total_time().as_secs(),
+            ingredient_count: ingredients.len(),
+            material_count: materials.len(),
+            ingredients,
+        }
+    }
+}
+
+/// Duration of a timer named after a metadata key, or else of its own text
+fn resolve_timer(metadata: &HashMap<String, String>, duration: &str) -> Option<Duration> {
+    let name = duration.trim().to_lowercase();
+    metadata
+        .iter()
+        .find(|(key, _)| key.trim().to_lowercase() == name)
+        .and_then(|(_, value)| parse_duration(value))
+        .or_else(|| parse_duration(duration))
+}
+
+/// Instructions of a recipe to merge with another one, under a section with
+/// its title and without the metadata and the backstory
+fn merged_instructions<'b>(recipe: &'b Recipe) -> Vec<Token<'b>> {
+    let mut instructions = vec![];
+    let mut tokens = recipe.instructions.iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Metadata { .. } => {
+                tokens.next_if(|token| matches!(token, Token::Space(_)));
+            }
+            Token::Backstory(_) => {}
+            Token::Space(_) if instructions.is_empty() => {}
+            token => instructions.push(token.clone()),
+        }
+    }
+    while matches!(instructions.last(), Some(Token::Space(_))) {
+        instructions.pop();
+    }
+    let title = recipe.title();
+    let starts_with_title =
+        matches!(instructions.first(), Some(Token::Section(section)) if Some(*section) == title);
+    if let Some(title) = title
+        && !starts_with_title
+        && !instructions.is_empty()
+    {
+        instructions.splice(0..0, [Token::Section(title), Token::Space("\n")]);
+    }
+    instructions
+}
+
+/// Position of the ingredient a comment belongs to: the following one,
+/// or else the previous one on the same line. A comment spanning lines is
+/// a commented out part of the recipe, not a note.
+fn note_target(tokens: &[Token], comment: usize) -> Option<usize> {
+    if matches!(tokens[comment], Token::Comment(comment) if comment.contains('\n')) {
+        return None;
+    }
+    let next = tokens[comment + 1..]
+        .iter()
+        .position(|token| !matches!(token, Token::Space(_)))
+        .map(|offset| comment + 1 + offset);
+    if let Some(next) = next
+        && matches!(tokens[next], Token::Ingredient { .. })
+    {
+        return Some(next);
+    }
+    let previous = tokens[..comment]
+        .iter()
+        .rposition(|token| !matches!(token, Token::Space(v) if !v.contains('\n')))?;
+    matches!(tokens[previous], Token::Ingredient { .. }).then_some(previous)
+}
+
+impl<'a> TryFrom<&'a str> for Recipe<'a> {
+    type Error = RecipeError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let leading = value.len() - value.trim_start().len();
+        // The offset of the error is relative to the given value, not the trimmed one
+        let tokens = parse_spanned(value.trim()).map_err(|err| {
+            let mut err = RecipeError::from(err);
+            err.offset += leading;
+            err
+        })?;
+        let tokens = tokens
+            .into_iter()
+            .map(|(token, span)| (token, span.start + leading..span.end + leading))
+            .collect();
+        Ok(Self::from_spanned_tokens(tokens, &RecipeOptions::default()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{display_amounts, parse};
+    use rstest::*;
+
+    #[test]
+    fn test_invalid_recipes() {
+        let invalid_recipe = "
+        >> name: invalid-recipe
+        this is an {invalid recipe
+        ";
+        let recipe = Recipe::try_from(invalid_recipe);
+        println!("{recipe:?}");
+        let err = recipe.unwrap_err();
+        assert_eq!(&invalid_recipe[err.offset..err.offset + 1], "{");
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("  \n\t\n ")]
+    fn test_recipe_empty(#[case] input: &str) {
+        let recipe = Recipe::try_from(input).unwrap();
+        assert!(recipe.instructions.is_empty());
+        assert!(recipe.ingredients.is_empty());
+        assert!(recipe.steps().is_empty());
+        assert_eq!(recipe.title(), None);
+        assert_eq!(recipe.summary().ingredient_count, 0);
+    }
+
+    #[test]
+    fn test_recipe_commented_out_steps() {
+        let input = "Boil the {water}(1 l) in a &{pot}.\n/* Add the {salt}(1 pinch)\ntill it's @{broth}\nin a &{pan} for t{5 min}. */\nAdd the {rice}.";
+        let recipe = Recipe::try_from(input).unwrap();
+        let names: Vec<&str> = recipe.ingredients.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["water", "rice"]);
+        let materials: Vec<&str> = recipe.materials.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(materials, vec!["pot"]);
+        assert!(recipe.timers.is_empty());
+        assert!(recipe.recipes_refs.is_empty());
+        assert_eq!(
+            recipe.steps(),
+            vec!["Boil the water in a pot.", "Add the rice."]
+        );
+        let shopping: Vec<String> = aggregate(&recipe.instructions, None)
+            .into_iter()
+            .map(|ingredient| ingredient.name)
+            .collect();
+        assert_eq!(shopping, vec!["water", "rice"]);
+    }
+
+    #[rstest]
+    #[case(">> name: Soup\n>> title: Hot soup\n= Broth\nBoil", Some("Soup"))]
+    #[case(">> title: Hot soup\n= Broth\nBoil", Some("Hot soup"))]
+    #[case("Boil\n= Broth\nBoil\n= Garnish", Some("Broth"))]
+    #[case("Boil the {water}", None)]
+    fn test_recipe_title(#[case] input: &str, #[case] expected: Option<&str>) {
+        let recipe = Recipe::try_from(input).expect("failed to parse");
+        assert_eq!(recipe.title(), expected);
+    }
+
+    #[rstest]
+    #[case("Add /* organic */ {tomatoes}(3)", Some("organic"))]
+    #[case("Add {tomatoes}(3) /* organic */ and stir", Some("organic"))]
+    #[case("Add {tomatoes}(3) // organic\nStir", Some("organic"))]
+    #[case("/* ripe */ {tomatoes} // organic", Some("ripe organic"))]
+    #[case("Add {tomatoes}(3)\n// organic", None)]
+    #[case("Add {tomatoes}(3) and /* organic */ stir", None)]
+    #[case("/* Add the {salt}\nand stir */\nAdd {tomatoes}(3)", None)]
+    fn test_recipe_comment_notes(#[case] input: &str, #[case] expected: Option<&str>) {
+        let tokens = parse(input).expect("failed to parse");
+        let options = RecipeOptions {
+            comment_notes: true,
+            ..Default::default()
+        };
+        let recipe = Recipe::from_tokens_with_options(tokens.clone(), &options);
+        assert_eq!(recipe.ingredients[0].note.as_deref(), expected);
+        assert_eq!(recipe.instructions, tokens);
+
+        let recipe = Recipe::from_tokens(tokens);
+        assert_eq!(recipe.ingredients[0].note, None);
+    }
+
+    #[test]
+    fn test_recipe_steps() {
+        let input = ">> name: soup\n= Broth\nBoil the {water}.\n\n  Add {salt}.  \n---\nA story";
+        let recipe = Recipe::try_from(input).expect("failed to parse");
+        assert_eq!(recipe.steps(), vec!["Boil the water.", "Add salt."]);
+    }
+
+    #[test]
+    fn test_recipe_ingredients_by_section() {
+        let input = "Preheat the oven with {oil}.\n= For the dough\nMix the {flour}(200 gr) with {water}.\n= Nothing to add\nRest.\n= For the filling\nAdd the {flour}(50 gr), {sugar} and the rest of the {Water}.";
+        let recipe = Recipe::try_from(input).expect("failed to parse");
+        let sections = recipe.ingredients_by_section();
+        let names: Vec<(Option<&str>, Vec<&str>)> = sections
+            .iter()
+            .map(|(section, ingredients)| {
+                (
+                    section.as_deref(),
+                    ingredients.iter().map(|i| i.name.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                (None, vec!["oil"]),
+                (Some("For the dough"), vec!["flour", "water"]),
+                (Some("For the filling"), vec!["flour", "sugar"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recipe_ingredients_by_section_without_sections() {
+        let recipe = Recipe::try_from("Boil the {potatoes}(3)").expect("failed to parse");
+        let sections = recipe.ingredients_by_section();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].0, None);
+        assert!(
+            Recipe::try_from("= Empty")
+                .unwrap()
+                .ingredients_by_section()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_recipe_strip_backstory() {
+        let input = ">> name: soup\nBoil the {water}.\nServe.\n---\nA story about {soup}";
+        let options = RecipeOptions {
+            strip_backstory: true,
+            ..Default::default()
+        };
+        let recipe = Recipe::from_tokens_with_options(parse(input).unwrap(), &options);
+        let full = Recipe::from_tokens(parse(input).unwrap());
+        assert_eq!(recipe.backstory, None);
+        assert!(full.backstory.is_some());
+        assert_eq!(recipe.steps(), full.steps());
+        assert_eq!(recipe.metadata, full.metadata);
+        assert_eq!(recipe.ingredients, full.ingredients);
+    }
+
+    #[test]
+    fn test_recipe_resolve_percentages() {
+        let input = "Mix the {flour}(400 gr) and {Flour}(100 gr) with {salt}(2% flour), {yeast}(1.5 % FLOUR), {sugar}(5% honey), {oil}(3% water), {water}(1 glass / 200 ml), {milk}(1 cup), {milk}(100 ml), {butter}(10% milk), {honey}, {egg}(1% salt)";
+        let recipe = Recipe::try_from(input).expect("failed to parse");
+        let (amounts, lints) = recipe.resolve_percentages();
+        assert_eq!(
+            amounts,
+            vec![
+                ResolvedAmount {
+                    index: 2,
+                    quantity: Quantity::new(10, 1).unwrap(),
+                    unit: Some("gr".to_string())
+                },
+                ResolvedAmount {
+                    index: 3,
+                    quantity: Quantity::new(15, 2).unwrap(),
+                    unit: Some("gr".to_string())
+                },
+                ResolvedAmount {
+                    index: 5,
+                    quantity: Quantity::new(3, 100).unwrap(),
+                    unit: Some("glass".to_string())
+                },
+            ]
+        );
+        let messages: Vec<String> = lints.into_iter().map(|lint| lint.message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "the amount of `sugar` cannot be computed, `honey` has no numeric amount",
+                "the amount of `butter` cannot be computed, `milk` has amounts in more than one unit",
+                "the amount of `egg` cannot be computed, `salt` is a percentage too",
+            ]
+        );
+        assert_eq!(
+            recipe.ingredients[2].amounts,
+            vec![Amount::new(Some("2"), Some("% flour"))]
+        );
+    }
+
+    #[test]
+    fn test_recipe_resolve_percentages_missing_ingredient() {
+        let recipe = Recipe::try_from("Add {salt}(2% flour)").expect("failed to parse");
+        let (amounts, lints) = recipe.resolve_percentages();
+        assert!(amounts.is_empty());
+        assert_eq!(
+            lints[0].message,
+            "the amount of `salt` cannot be computed, `flour` is not in the recipe"
+        );
+    }
+
+    #[test]
+    fn test_recipe_flow_graph() {
+        let input = "= Dough\nMix the {flour}(200 gr) and {water} in a &{bowl}.\n\nKnead the {dough} /* well */.\n= Sauce\nChop the {tomatoes} and {Garlic}.\nFry the {garlic} in a &{pan}, add the {tomatoes}.\nClean the &{Bowl}.\nRest.\n---\nA story with {flour}";
+        let recipe = Recipe::try_from(input).expect("failed to parse");
+        let graph = recipe.flow_graph();
+        let steps: Vec<&str> = graph.nodes.iter().map(|n| n.step.as_str()).collect();
+        assert_eq!(steps, recipe.steps());
+        assert_eq!(graph.nodes[0].ingredients, vec!["flour", "water"]);
+        assert_eq!(graph.nodes[0].materials, vec!["bowl"]);
+        assert_eq!(graph.nodes[2].ingredients, vec!["tomatoes", "Garlic"]);
+        assert_eq!(graph.edges, vec![(0, 4), (2, 3)]);
+        assert_eq!(graph.dependencies(3), vec![2]);
+        assert!(graph.dependencies(1).is_empty());
+    }
+
+    #[test]
+    fn test_recipe_flow_graph_chains_repeated_uses() {
+        let recipe =
+            Recipe::try_from("Add {salt}.\nAdd more {salt}.\nAdd {salt} again, and {salt}.")
+                .expect("failed to parse");
+        assert_eq!(recipe.flow_graph().edges, vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_recipe_rename_ingredient() {
+        let input = "Melt the {butter }(50 gr) in a &{butter dish}.\nAdd the {flour} and the rest of the {BUTTER}, not the {butter beans} nor @{butter}.";
+        let mut recipe = Recipe::try_from(input).expect("failed to parse");
+        recipe.rename_ingredient(" Butter", "margarine");
+        let names: Vec<&str> = recipe.ingredients.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["margarine", "flour", "margarine", "butter beans"]
+        );
+        assert_eq!(
+            recipe.ingredients[0].amounts,
+            vec![Amount::new(Some("50"), Some("gr"))]
+        );
+        assert_eq!(recipe.recipes_refs[0].name, "butter");
+        assert_eq!(
+            recipe.steps(),
+            vec![
+                "Melt the margarine in a butter dish.",
+                "Add the flour and the rest of the margarine, not the butter beans nor \"butter\"."
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("Mix the {butter}(113 gr / 1 stick) with {eggs}(3)", vec!["{butter}(113 gr / 1 stick)", "{eggs}(3)"])]
+    #[case("  \n Add {salt} /* to taste */", vec!["{salt}"])]
+    #[case("= Dough\n{olive oil}( 2 tbsp )\n---\nWith {love}", vec!["{olive oil}( 2 tbsp )"])]
+    #[case("Add the {sauce} and @{sauce}(1 cup)", vec!["{sauce}"])]
+    fn test_recipe_ingredient_spans(#[case] source: &str, #[case] expected: Vec<&str>) {
+        let recipe = Recipe::try_from(source).unwrap();
+        let spans: Vec<&str> = recipe
+            .ingredients
+            .iter()
+            .map(|ingredient| &source[ingredient.span.clone().unwrap()])
+            .collect();
+        assert_eq!(spans, expected);
+    }
+
+    #[test]
+    fn test_recipe_rename_ingredient_percentages() {
+        let input = "Mix {flour}(500 gr), {salt}(2% flour) and {sugar}(1 % Flour / 5 gr)";
+        let mut recipe = Recipe::try_from(input).expect("failed to parse");
+        let to = String::from("rye");
+        recipe.rename_ingredient("flour", to);
+        assert_eq!(recipe.ingredients[0].name, "rye");
+        assert_eq!(recipe.ingredients[1].amounts[0].to_string(), "2% rye");
+        assert_eq!(
+            recipe.ingredients[1].amounts[0].percentage().unwrap().of,
+            "rye"
+        );
+        assert_eq!(
+            display_amounts(&recipe.ingredients[2].amounts),
+            "1% rye / 5 gr"
+        );
+        let percentages: Vec<String> = recipe
+            .instructions
+            .iter()
+            .filter_map(|token| match token {
+                Token::Ingredient { amounts, .. } => amounts.first()?.percentage(),
+                _ => None,
+            })
+            .map(|percentage| percentage.of.to_string())
+            .collect();
+        assert_eq!(percentages, vec!["rye", "rye"]);
+    }
+
+    #[test]
+    fn test_recipe_spans_with_options() {
+        let source = "{water}(1 l)\n---\nstory";
+        let options = RecipeOptions {
+            strip_backstory: true,
+            ..Default::default()
+        };
+        let recipe = Recipe::from_spanned_tokens(parse_spanned(source).unwrap(), &options);
+        assert_eq!(recipe.ingredients[0].span, Some(0..12));
+        assert!(recipe.backstory.is_none());
+
+        let source = "Add @{sauce}(1 cup)";
+        let recipe = Recipe::try_from(source).unwrap();
+        assert_eq!(
+            &source[recipe.recipes_refs[0].span.clone().unwrap()],
+            "@{sauce}(1 cup)"
+        );
+        assert_eq!(
+            Recipe::from_tokens(parse(source).unwrap()).recipes_refs[0].span,
+            None
+        );
+    }
+
+    #[test]
+    fn test_recipe_merge() {
+        let main = Recipe::try_from(
+            ">> name: Steak\n>> servings: 2\nGrill the {steak}(1) t{5 min}\n---\nFrom grandma",
+        )
+        .unwrap();
+        let side = Recipe::try_from(
+            ">> servings: 4\n>> diet: vegan\n= Salad\nToss the {lettuce}\n\nAdd the {salt}\n---\nFresh",
+        )
+        .unwrap();
+        let meal = main.merge(&side);
+        assert_eq!(
+            meal.instructions,
+            parse("= Steak\nGrill the {steak}(1) t{5 min}\n\n= Salad\nToss the {lettuce}\n\nAdd the {salt}")
+                .unwrap()
+        );
+        assert_eq!(meal.name.as_deref(), Some("Steak"));
+        assert_eq!(meal.metadata["servings"], "2");
+        assert_eq!(meal.metadata["diet"], "vegan");
+        assert_eq!(meal.backstory.as_deref(), Some("From grandma\n\nFresh"));
+        assert_eq!(meal.timers.len(), 1);
+        let sections: Vec<(Option<String>, usize)> = meal
+            .ingredients_by_section()
+            .into_iter()
+            .map(|(section, ingredients)| (section, ingredients.len()))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                (Some("Steak".to_string()), 1),
+                (Some("Salad".to_string()), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recipe_merge_aggregates_ingredients() {
+        let main = Recipe::try_from("Salt the {steak} with {salt}(1 pinch)").unwrap();
+        let side = Recipe::try_from("Salt the {potatoes} with {salt}(2 pinch)").unwrap();
+        let meal = main.merge(&side);
+        assert_eq!(meal.steps().len(), 2);
+        let salt = aggregate(&meal.instructions, None)
+            .into_iter()
+            .find(|ingredient| ingredient.name == "salt")
+            .unwrap();
+        assert_eq!(salt.totals.len(), 1);
+        assert_eq!(main.merge(&Recipe::from_tokens(vec![])), main);
+    }
+
+    #[rstest]
+    #[case("Stir t{5 min}", 5, 0)]
+    #[case("Stir t{5 min}!active", 5, 0)]
+    #[case("Rest t{5 min}!passive", 0, 5)]
+    #[case("Stir t{5 min} and rest t{1 h}!passive, t{soon}!passive", 5, 60)]
+    #[case("Rest t{5 min}!passively", 5, 0)]
+    fn test_recipe_active_passive_time(
+        #[case] input: &str,
+        #[case] active: u64,
+        #[case] passive: u64,
+    ) {
+        let recipe = Recipe::try_from(input).unwrap();
+        assert_eq!(recipe.active_time(), Duration::from_secs(active * 60));
+        assert_eq!(recipe.passive_time(), Duration::from_secs(passive * 60));
+        assert_eq!(
+            recipe.total_time(),
+            recipe.active_time() + recipe.passive_time()
+        );
+    }
+
+    #[test]
+    fn test_recipe_timers_named_in_metadata() {
+        let input = ">> Rest: 30 minutes\n>> proof: overnight\nRest t{rest}, then t{10 minutes}, then t{proof} and t{chill}";
+        let recipe = Recipe::try_from(input).expect("failed to parse");
+        let resolved: Vec<Option<Duration>> = recipe.timers.iter().map(|t| t.resolved).collect();
+        assert_eq!(
+            resolved,
+            vec![
+                Some(Duration::from_secs(30 * 60)),
+                Some(Duration::from_secs(10 * 60)),
+                None,
+                None
+            ]
+        );
+        assert_eq!(recipe.timers[0].duration, "rest");
+        assert_eq!(recipe.total_time(), Duration::from_secs(40 * 60));
+    }
+
+    #[rstest]
+    #[case("4", Some(Servings::Exact(4)))]
+    #[case(" 4 people", Some(Servings::Exact(4)))]
+    #[case("4-6", Some(Servings::Range(4, 6)))]
+    #[case("4 - 6 people", Some(Servings::Range(4, 6)))]
+    #[case("4–6", Some(Servings::Range(4, 6)))]
+    #[case("4 to 6", Some(Servings::Range(4, 6)))]
+    #[case("4-4", Some(Servings::Exact(4)))]
+    #[case("6-4", None)]
+    #[case("4-", Some(Servings::Exact(4)))]
+    #[case("a few", None)]
+    #[case("-4", None)]
+    fn test_servings_parse(#[case] input: &str, #[case] expected: Option<Servings>) {
+        assert_eq!(Servings::parse(input), expected);
+    }
+
+    #[test]
+    fn test_recipe_servings() {
+        let recipe = Recipe::try_from(">> servings: 4-6 people\nBoil").unwrap();
+        let servings = recipe.servings().unwrap();
+        assert_eq!(servings, Servings::Range(4, 6));
+        assert_eq!((servings.min(), servings.max()), (4, 6));
+        assert_eq!(servings.to_string(), "4-6");
+    }
+
+    #[test]
+    fn test_recipe_nutrition() {
+        let input = ">> Calories: 2000 kcal\n>> protein: 80g\n>> fat: a lot\n>> fiber: 12\n>> color: 3 red\nMix";
+        let recipe = Recipe::try_from(input).unwrap();
+        let nutrition = recipe.nutrition().unwrap();
+        let facts: Vec<(&str, Option<Quantity>, Option<&str>)> = nutrition
+            .facts
+            .iter()
+            .map(|fact| {
+                (
+                    fact.name.as_str(),
+                    Some(fact.quantity),
+                    fact.unit.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            facts,
+            vec![
+                ("calories", Quantity::new(2000, 1), Some("kcal")),
+                ("fiber", Quantity::new(12, 1), None),
+                ("protein", Quantity::new(80, 1), Some("g")),
+            ]
+        );
+        assert_eq!(
+            nutrition.get(" Protein").map(|fact| fact.quantity),
+            Quantity::new(80, 1)
+        );
+        assert!(
+            Recipe::try_from(">> servings: 2\nMix")
+                .unwrap()
+                .nutrition()
+                .is_none()
+        );
+    }
+
+    #[rstest]
+    #[case(">> servings: 4\n>> protein: 100 g\nMix", Quantity::new(25, 1))]
+    #[case(
+        ">> servings: 4-6 people\n>> protein: 100 g\nMix",
+        Quantity::new(20, 1)
+    )]
+    #[case(">> servings: 3\n>> protein: 100 g\nMix", Quantity::new(100, 3))]
+    #[case(">> protein: 100 g\nMix", None)]
+    #[case(">> servings: 4\nMix", None)]
+    #[case(">> servings: 0\n>> protein: 100 g\nMix", None)]
+    fn test_recipe_nutrition_per_serving(#[case] input: &str, #[case] expected: Option<Quantity>) {
+        let recipe = Recipe::try_from(input).unwrap();
+        let protein = recipe
+            .nutrition_per_serving()
+            .and_then(|nutrition| Some(nutrition.get("protein")?.quantity));
+        assert_eq!(protein, expected);
+    }
+
+    #[test]
+    fn test_recipe_summary() {
+        let input = ">> name: potato soup
+>> servings: 2
+
+Boil the {potatoes}(3) and the {Potatoes }(1) in a &{pot} for t{20 minutes}.
+Blend with a &{blender} and the &{Pot} for t{1 min}, then t{until smooth}.
+Add {salt}.";
+        let recipe = Recipe::try_from(input).expect("failed to parse");
+        let summary = recipe.summary();
+        assert_eq!(
+            summary,
+            RecipeSummary {
+                title: Some("potato soup".to_string()),
+                servings: Some(Servings::Exact(2)),
+                total_time_seconds: 21 * 60,
+                ingredient_count: 2,
+                material_count: 2,
+                ingredients: vec!["potatoes".to_string(), "salt".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_recipe_summary_serialization() {
+        let recipe = Recipe::try_from("{salt}").unwrap();
+        let serialized = serde_json::to_string(&recipe.summary()).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"title":null,"servings":null,"total_time_seconds":0,"ingredient_count":1,"material_count":0,"ingredients":["salt"]}"#
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use crate::{IngredientSort, Recipe, Token, Total, aggregate, display_amounts, sort_ingredients};
+
+/// Where the list of ingredients is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IngredientList {
+    /// Only the steps are written
+    #[default]
+    Hidden,
+    /// The conventional layout: ingredients, then method
+    BeforeSteps,
+    AfterSteps,
+}
+
+/// Options to control how the recipe text is rendered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Collapse runs of spaces into a single one and trim the leading and
+    /// trailing blank lines. Newlines between steps are kept.
+    pub collapse_whitespace: bool,
+    /// Write the ingredients as a list, without repetitions
+    pub ingredient_list: IngredientList,
+    /// Order of the ingredient list, the order of the recipe by default
+    pub ingredient_sort: IngredientSort,
+    /// Prefix of each item of the ingredient list, `- ` by default
+    pub bullet: Cow<'static, str>,
+    /// Write the amounts next to the ingredients in the steps, like `potatoes (3)`
+    pub inline_amounts: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            collapse_whitespace: false,
+            ingredient_list: IngredientList::Hidden,
+            ingredient_sort: IngredientSort::Source,
+            bullet: Cow::Borrowed("- "),
+            inline_amounts: false,
+        }
+    }
+}
+
+/// Render the tokens as plain text, the way a human would read the recipe.
+///
+/// By default the whitespace of the source is preserved.
+///
+/// Example:
+///
+/// ```
+/// use recipe_parser::{parse, render_text, RenderOptions};
+///
+/// let tokens = parse(">> name: potatoes\nBoil  the {potatoes}(3)").unwrap();
+/// let options = RenderOptions {
+///     collapse_whitespace: true,
+///     ..Default::default()
+/// };
+/// assert_eq!(render_text(&tokens, &options), "Boil the potatoes");
+/// ```
+///
+/// The ingredients can be listed before the steps:
+///
+/// ```
+/// use recipe_parser::{parse, render_text, IngredientList, RenderOptions};
+///
+/// let tokens = parse("Boil the {potatoes}(3) with {salt}").unwrap();
+/// let options = RenderOptions {
+///     ingredient_list: IngredientList::BeforeSteps,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     render_text(&tokens, &options),
+///     "- potatoes (3)\n- salt\n\nBoil the potatoes with salt"
+/// );
+/// ```
+pub fn render_text(tokens: &[Token], options: &RenderOptions) -> String {
+    let steps = render_steps(tokens, options);
+    let list = match options.ingredient_list {
+        IngredientList::Hidden => return steps,
+        _ => render_ingredient_list(tokens, options),
+    };
+    match (options.ingredient_list, list.is_empty(), steps.is_empty()) {
+        (_, true, _) => steps,
+        (_, false, true) => list,
+        (IngredientList::AfterSteps, false, false) => format!("{steps}\n\n{list}"),
+        _ => format!("{list}\n{steps}"),
+    }
+}
+
+/// One line per ingredient, with the amounts of each unit summed up
+fn render_ingredient_list(tokens: &[Token], options: &RenderOptions) -> String {
+    let mut out = String::new();
+    let mut ingredients = aggregate(tokens, None);
+    sort_ingredients(&mut ingredients, options.ingredient_sort);
+    for ingredient in ingredients {
+        let totals: Vec<String> = ingredient.totals.iter().map(render_total).collect();
+        write!(out, "{}{}", options.bullet, ingredient.name)
+            .expect("writing to a String cannot fail");
+        if !totals.is_empty() {
+            write!(out, " ({})", totals.join(", ")).expect("writing to a String cannot fail");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn render_total(total: &Total) -> String {
+    match (total.quantity, &total.unit) {
+        (Some(quantity), Some(unit)) => format!("{} {unit}", quantity.to_mixed_string()),
+        (Some(quantity), None) => quantity.to_mixed_string(),
+        (None, Some(unit)) => unit.clone(),
+        (None, None) => String::new(),
+    }
+}
+
+fn write_token(out: &mut String, token: &Token, options: &RenderOptions) {
+    match token {
+        Token::Word(v) | Token::Space(v) => {
+            out.push_str(v);
+            return;
+        }
+        Token::Ingredient { name, amounts } if options.inline_amounts && !amounts.is_empty() => {
+            write!(out, "{name} ({})", display_amounts(amounts))
+        }
+        token => write!(out, "{token}"),
+    }
+    .expect("writing to a String cannot fail");
+}
+
+fn render_steps(tokens: &[Token], options: &RenderOptions) -> String {
+    let mut out = String::new();
+    if !options.collapse_whitespace {
+        for token in tokens {
+            write_token(&mut out, token, options);
+        }
+        return out;
+    }
+
+    // Whitespace is held until the next visible token, so runs spanning
+    // several space tokens are collapsed together and trailing ones are dropped
+    let mut pending = String::new();
+    for token in tokens {
+        match token {
+            Token::Space(v) => pending.push_str(v),
+            Token::Metadata { .. } | Token::Comment(_) => {}
+            token => {
+                if !out.is_empty() && !pending.is_empty() {
+                    let newlines: String = pending
+                        .chars()
+                        .filter(|c| *c == '\n' || *c == '\r')
+                        .collect();
+                    if newlines.is_empty() {
+                        out.push(' ');
+                    } else {
+                        out.push_str(&newlines);
+                    }
+                }
+                pending.clear();
+                write_token(&mut out, token, options);
+            }
+        }
+    }
+    out
+}
+
+/// Write the ingredients as CSV, e.g: to import them in a spreadsheet or a
+/// shopping app. There's one row per ingredient, in the order of the recipe,
+/// with the first of its amounts split into a quantity and a unit. Missing
+/// values are empty cells.
+///
+/// An ingredient is optional when its note is `optional`, see
+/// [`RecipeOptions::comment_notes`](crate::RecipeOptions::comment_notes).
+///
+/// ```
+/// use recipe_parser::{Recipe, to_csv};
+///
+/// let recipe = Recipe::try_from("Mix {flour}(500 gr) with {salt, coarse}(2) and {pepper}").unwrap();
+/// assert_eq!(
+///     to_csv(&recipe),
+///     "name,quantity,unit,optional\nflour,500,gr,false\n\"salt, coarse\",2,,false\npepper,,,false\n"
+/// );
+/// ```
+pub fn to_csv(recipe: &Recipe) -> String {
+    let mut out = String::from("name,quantity,unit,optional\n");
+    for ingredient in &recipe.ingredients {
+        let amount = ingredient.amounts.first();
+        let optional = ingredient
+            .note
+            .as_deref()
+            .is_some_and(|note| note.trim().eq_ignore_ascii_case("optional"));
+        writeln!(
+            out,
+            "{},{},{},{optional}",
+            csv_field(&ingredient.name),
+            csv_field(
+                amount
+                    .and_then(|amount| amount.quantity)
+                    .unwrap_or_default()
+            ),
+            csv_field(
+                amount
+                    .and_then(|amount| amount.unit.as_deref())
+                    .unwrap_or_default()
+            ),
+        )
+        .expect("writing to a String cannot fail");
+    }
+    out
+}
+
+/// Quote the field when it holds a separator, a quote or a line break
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse;
+    use rstest::*;
+
+    #[rstest]
+    #[case("Boil  the {potatoes}", "Boil  the potatoes")]
+    #[case(">> name: potatoes\nBoil", "Boil")]
+    #[case(
+        ">> name: potatoes\r\n>> tags: easy\nBoil the {potatoes}",
+        "Boil the potatoes"
+    )]
+    #[case("Boil \t\n\n  Serve \n", "Boil \t\n\n  Serve \n")]
+    fn test_render_text_preserves_whitespace(#[case] input: &str, #[case] expected: &str) {
+        let tokens = parse(input).expect("failed to parse");
+        let text = render_text(&tokens, &RenderOptions::default());
+        assert_eq!(text, expected);
+    }
+
+    #[rstest]
+    #[case("Boil  the {potatoes}", "Boil the potatoes")]
+    #[case(">> name: potatoes\nBoil", "Boil")]
+    #[case("\n\n  Boil \t in a &{pot} \n", "Boil in a pot")]
+    #[case("Boil.  \n\n  Serve.", "Boil.\n\nServe.")]
+    #[case("Boil /* slowly */ the {potatoes}", "Boil the potatoes")]
+    fn test_render_text_collapse_whitespace(#[case] input: &str, #[case] expected: &str) {
+        let tokens = parse(input).expect("failed to parse");
+        let options = RenderOptions {
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        let text = render_text(&tokens, &options);
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn test_render_text_ingredient_list() {
+        let tokens = parse(
+            "Melt the {butter}(50 gr) with {sugar}(1/2 cup).\nAdd {butter}(1 tbsp) and {butter}(1/2 tbsp), then {salt}(1 pinch).",
+        )
+        .expect("failed to parse");
+        let mut options = RenderOptions {
+            collapse_whitespace: true,
+            ingredient_list: IngredientList::BeforeSteps,
+            ingredient_sort: IngredientSort::Source,
+            bullet: "* ".into(),
+            inline_amounts: false,
+        };
+        assert_eq!(
+            render_text(&tokens, &options),
+            "* butter (50 gr, 1 1/2 tbsp)\n* sugar (1/2 cup)\n* salt (1 pinch)\n\nMelt the butter with sugar.\nAdd butter and butter, then salt."
+        );
+
+        options.ingredient_list = IngredientList::AfterSteps;
+        options.inline_amounts = true;
+        assert_eq!(
+            render_text(&tokens, &options),
+            "Melt the butter (50 gr) with sugar (1/2 cup).\nAdd butter (1 tbsp) and butter (1/2 tbsp), then salt (1 pinch).\n\n* butter (50 gr, 1 1/2 tbsp)\n* sugar (1/2 cup)\n* salt (1 pinch)\n"
+        );
+
+        options.ingredient_list = IngredientList::BeforeSteps;
+        options.ingredient_sort = IngredientSort::Alphabetical;
+        options.inline_amounts = false;
+        assert!(
+            render_text(&tokens, &options).starts_with(
+                "* butter (50 gr, 1 1/2 tbsp)\n* salt (1 pinch)\n* sugar (1/2 cup)\n\n"
+            )
+        );
+    }
+
+    #[rstest]
+    #[case("Boil the water", "Boil the water")]
+    #[case("{salt}", "- salt\n\nsalt")]
+    fn test_render_text_ingredient_list_edges(#[case] input: &str, #[case] expected: &str) {
+        let tokens = parse(input).expect("failed to parse");
+        let options = RenderOptions {
+            ingredient_list: IngredientList::BeforeSteps,
+            ..Default::default()
+        };
+        assert_eq!(render_text(&tokens, &options), expected);
+    }
+
+    #[rstest]
+    #[case("", "name,quantity,unit,optional\n")]
+    #[case("{eggs}(3)", "name,quantity,unit,optional\neggs,3,,false\n")]
+    #[case("{salt}(1 pinch)", "name,quantity,unit,optional\nsalt,1,pinch,false\n")]
+    #[case(
+        "{butter}(113 gr / 1 stick)",
+        "name,quantity,unit,optional\nbutter,113,gr,false\n"
+    )]
+    #[case(
+        "{salt, coarse} and {oil}(1 tbsp)",
+        "name,quantity,unit,optional\n\"salt, coarse\",,,false\noil,1,tbsp,false\n"
+    )]
+    #[case(
+        "{salt}(1) and {salt}(2)",
+        "name,quantity,unit,optional\nsalt,1,,false\nsalt,2,,false\n"
+    )]
+    fn test_to_csv(#[case] input: &str, #[case] expected: &str) {
+        let recipe = Recipe::try_from(input).unwrap();
+        assert_eq!(to_csv(&recipe), expected);
+    }
+
+    #[rstest]
+    #[case("salt", "salt")]
+    #[case("salt, coarse", "\"salt, coarse\"")]
+    #[case("12\" pan", "\"12\"\" pan\"")]
+    #[case("two\nlines", "\"two\nlines\"")]
+    fn test_csv_field(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(csv_field(value), expected);
+    }
+
+    #[test]
+    fn test_to_csv_optional() {
+        let options = crate::RecipeOptions {
+            comment_notes: true,
+            ..Default::default()
+        };
+        let tokens =
+            parse("Add {salt} /* coarse */ and /* Optional */ {parsley}(1 bunch)").unwrap();
+        let recipe = Recipe::from_tokens_with_options(tokens, &options);
+        assert_eq!(
+            to_csv(&recipe),
+            "name,quantity,unit,optional\nsalt,,,false\nparsley,1,bunch,true\n"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Token;
+
+/// Plain text, or a token with a meaning of its own, see [`text_spans`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "span", content = "content"))]
+pub enum TextSpan<'a> {
+    /// Consecutive words and spaces, as written
+    Text(String),
+    Token(Token<'a>),
+}
+
+/// The tokens without the backstory, to share just the recipe
+///
+/// ```
+/// use recipe_parser::{parse, strip_backstory, Token};
+///
+/// let tokens = parse("Boil the {water}\n---\nMy grandma's recipe").unwrap();
+/// assert!(!strip_backstory(&tokens).iter().any(|t| matches!(t, Token::Backstory(_))));
+/// ```
+pub fn strip_backstory<'a>(tokens: &[Token<'a>]) -> Vec<Token<'a>> {
+    tokens
+        .iter()
+        .filter(|token| !matches!(token, Token::Backstory(_)))
+        .cloned()
+        .collect()
+}
+
+/// The tokens without the `/* */` and `//` comments
+pub fn strip_comments<'a>(tokens: &[Token<'a>]) -> Vec<Token<'a>> {
+    tokens
+        .iter()
+        .filter(|token| !matches!(token, Token::Comment(_)))
+        .cloned()
+        .collect()
+}
+
+/// Join the consecutive words and spaces into a single text, e.g: to show
+/// a step in a template without going through every word.
+///
+/// ```
+/// use recipe_parser::{parse, text_spans, TextSpan, TimerKind, Token};
+///
+/// let tokens = parse("Boil for t{5 min} in a &{pot}.").unwrap();
+/// assert_eq!(
+///     text_spans(&tokens),
+///     vec![
+///         TextSpan::Text("Boil for ".to_string()),
+///         TextSpan::Token(Token::Timer { duration: "5 min", kind: TimerKind::Active }),
+///         TextSpan::Text(" in a ".to_string()),
+///         TextSpan::Token(Token::Material("pot")),
+///         TextSpan::Text(".".to_string()),
+///     ]
+/// );
+/// ```
+pub fn text_spans<'a>(tokens: &[Token<'a>]) -> Vec<TextSpan<'a>> {
+    let mut spans = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Word(text) | Token::Space(text) => match spans.last_mut() {
+                Some(TextSpan::Text(current)) => current.push_str(text),
+                _ => spans.push(TextSpan::Text(String::from(*text))),
+            },
+            token => spans.push(TextSpan::Token(token.clone())),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Amount, TimerKind, metadata_pairs, parse};
+    use alloc::vec;
+    use rstest::*;
+
+    const INPUT: &str = ">> name: soup\n>> tags: vegan\nBoil the {water} /* hot */ with {salt}(1 pinch).\n// taste it\nServe.\n---\nA story about {soup}";
+
+    #[test]
+    fn test_strip_backstory_keeps_the_rest() {
+        let tokens = parse(INPUT).unwrap();
+        let stripped = strip_backstory(&tokens);
+        assert_eq!(stripped.len(), tokens.len() - 1);
+        assert_eq!(stripped[..], tokens[..tokens.len() - 1]);
+        assert_eq!(metadata_pairs(&stripped), metadata_pairs(&tokens));
+        assert_eq!(strip_backstory(&stripped), stripped);
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let tokens = parse(INPUT).unwrap();
+        let stripped = strip_comments(&tokens);
+        assert_eq!(stripped.len(), tokens.len() - 2);
+        assert!(!stripped.iter().any(|t| matches!(t, Token::Comment(_))));
+        assert!(stripped.iter().any(|t| matches!(t, Token::Backstory(_))));
+    }
+
+    #[rstest]
+    #[case("", vec![])]
+    #[case("Boil the water", vec![TextSpan::Text("Boil the water".into())])]
+    #[case(
+        "{salt}(1 pinch) to taste",
+        vec![
+            TextSpan::Token(Token::Ingredient {
+                name: "salt".into(),
+                amounts: vec![Amount::new(Some("1"), Some("pinch"))],
+            }),
+            TextSpan::Text(" to taste".into()),
+        ]
+    )]
+    #[case(
+        "Add\n{salt} /* a lot */",
+        vec![
+            TextSpan::Text("Add\n".into()),
+            TextSpan::Token(Token::Ingredient { name: "salt".into(), amounts: vec![] }),
+            TextSpan::Text(" ".into()),
+            TextSpan::Token(Token::Comment("a lot")),
+        ]
+    )]
+    #[case(
+        "&{pot}t{5 min}",
+        vec![
+            TextSpan::Token(Token::Material("pot")),
+            TextSpan::Token(Token::Timer {
+                duration: "5 min",
+                kind: TimerKind::Active,
+            }),
+        ]
+    )]
+    fn test_text_spans(#[case] input: &str, #[case] expected: Vec<TextSpan>) {
+        assert_eq!(text_spans(&parse(input).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_text_spans_keeps_the_text() {
+        let tokens = parse(INPUT).unwrap();
+        let text: String = text_spans(&tokens)
+            .iter()
+            .filter_map(|span| match span {
+                TextSpan::Text(text) => Some(text.as_str()),
+                TextSpan::Token(_) => None,
+            })
+            .collect();
+        let words: String = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Word(text) | Token::Space(text) => Some(*text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, words);
+    }
+}
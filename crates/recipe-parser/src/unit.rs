@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::Quantity;
+
+/// Canonical unit of an amount
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Unit {
+    Milligram,
+    Gram,
+    Kilogram,
+    Milliliter,
+    Centiliter,
+    Deciliter,
+    Liter,
+    Teaspoon,
+    Tablespoon,
+    Cup,
+    FluidOunce,
+    Ounce,
+    Pound,
+    Pinch,
+    /// A unit the registry doesn't know, as it was written
+    Custom(String),
+}
+
+/// What an amount measures, e.g: to pick an icon for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum AmountKind {
+    Weight,
+    Volume,
+    /// A number of things, like `3` eggs or `2 cloves`
+    Count,
+    /// Neither a number nor a unit, like `to taste`, or a percentage
+    Unknown,
+}
+
+impl Unit {
+    /// Short form of the unit, like `g` or `tbsp`
+    pub fn symbol(&self) -> &str {
+        match self {
+            Unit::Milligram => "mg",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Milliliter => "ml",
+            Unit::Centiliter => "cl",
+            Unit::Deciliter => "dl",
+            Unit::Liter => "l",
+            Unit::Teaspoon => "tsp",
+            Unit::Tablespoon => "tbsp",
+            Unit::Cup => "cup",
+            Unit::FluidOunce => "fl oz",
+            Unit::Ounce => "oz",
+            Unit::Pound => "lb",
+            Unit::Pinch => "pinch",
+            Unit::Custom(unit) => unit,
+        }
+    }
+
+    /// True for the units that count something instead of measuring it, like
+    /// `cloves`, `bunch` or `large`. They're never converted to other units.
+    ///
+    /// ```
+    /// use recipe_parser::UnitRegistry;
+    ///
+    /// let registry = UnitRegistry::new();
+    /// assert!(registry.resolve("cloves").is_count());
+    /// assert!(!registry.resolve("grams").is_count());
+    /// ```
+    pub fn is_count(&self) -> bool {
+        matches!(self, Unit::Custom(_))
+    }
+
+    /// Whether the unit measures a weight or a volume, the units the
+    /// registry doesn't know count things
+    pub fn kind(&self) -> AmountKind {
+        match self {
+            Unit::Milligram | Unit::Gram | Unit::Kilogram | Unit::Ounce | Unit::Pound => {
+                AmountKind::Weight
+            }
+            Unit::Milliliter
+            | Unit::Centiliter
+            | Unit::Deciliter
+            | Unit::Liter
+            | Unit::Teaspoon
+            | Unit::Tablespoon
+            | Unit::Cup
+            | Unit::FluidOunce
+            | Unit::Pinch => AmountKind::Volume,
+            Unit::Custom(_) => AmountKind::Count,
+        }
+    }
+
+    /// How many grams a weight is, or milliliters a volume, when the unit
+    /// has an exact size. Cups, spoons and fluid ounces are the US ones, a
+    /// pinch and the units the registry doesn't know have no size.
+    pub(crate) fn base_factor(&self) -> Option<Quantity> {
+        let (numerator, denominator) = match self {
+            Unit::Milligram => (1, 1000),
+            Unit::Gram | Unit::Milliliter => (1, 1),
+            Unit::Kilogram | Unit::Liter => (1000, 1),
+            Unit::Centiliter => (10, 1),
+            Unit::Deciliter => (100, 1),
+            Unit::Teaspoon => (157725491, 32000000),
+            Unit::Tablespoon => (473176473, 32000000),
+            Unit::Cup => (473176473, 2000000),
+            Unit::FluidOunce => (473176473, 16000000),
+            Unit::Ounce => (45359237, 1600000),
+            Unit::Pound => (45359237, 100000),
+            Unit::Pinch | Unit::Custom(_) => return None,
+        };
+        Quantity::new(numerator, denominator)
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+const ALIASES: &[(&str, Unit)] = &[
+    ("mg", Unit::Milligram),
+    ("milligram", Unit::Milligram),
+    ("milligramme", Unit::Milligram),
+    ("g", Unit::Gram),
+    ("gr", Unit::Gram),
+    ("grm", Unit::Gram),
+    ("gram", Unit::Gram),
+    ("gramme", Unit::Gram),
+    ("kg", Unit::Kilogram),
+    ("kilo", Unit::Kilogram),
+    ("kilogram", Unit::Kilogram),
+    ("kilogramme", Unit::Kilogram),
+    ("ml", Unit::Milliliter),
+    ("milliliter", Unit::Milliliter),
+    ("millilitre", Unit::Milliliter),
+    ("cl", Unit::Centiliter),
+    ("centiliter", Unit::Centiliter),
+    ("centilitre", Unit::Centiliter),
+    ("dl", Unit::Deciliter),
+    ("deciliter", Unit::Deciliter),
+    ("decilitre", Unit::Deciliter),
+    ("l", Unit::Liter),
+    ("lt", Unit::Liter),
+    ("liter", Unit::Liter),
+    ("litre", Unit::Liter),
+    ("tsp", Unit::Teaspoon),
+    ("teaspoon", Unit::Teaspoon),
+    ("tbsp", Unit::Tablespoon),
+    ("tbs", Unit::Tablespoon),
+    ("tablespoon", Unit::Tablespoon),
+    ("cup", Unit::Cup),
+    ("c", Unit::Cup),
+    ("fl oz", Unit::FluidOunce),
+    ("fl. oz", Unit::FluidOunce),
+    ("fluid ounce", Unit::FluidOunce),
+    ("oz", Unit::Ounce),
+    ("ounce", Unit::Ounce),
+    ("lb", Unit::Pound),
+    ("pound", Unit::Pound),
+    ("pinch", Unit::Pinch),
+];
+
+/// Resolves the many spellings of a unit, like `gr`, `g` or `grams`,
+/// to a canonical [`Unit`].
+///
+/// Units are matched ignoring case and plurals, except for the usual
+/// `T` (tablespoon) and `t` (teaspoon).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitRegistry {
+    aliases: HashMap<String, Unit>,
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        let aliases = ALIASES
+            .iter()
+            .map(|(alias, unit)| (alias.to_string(), unit.clone()))
+            .collect();
+        Self { aliases }
+    }
+}
+
+impl UnitRegistry {
+    /// Registry with the built-in metric and US units
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the unit of an alias
+    pub fn with_alias(mut self, alias: &str, unit: Unit) -> Self {
+        self.aliases.insert(alias.trim().to_lowercase(), unit);
+        self
+    }
+
+    /// Canonical unit of the given text
+    ///
+    /// ```
+    /// use recipe_parser::{Unit, UnitRegistry};
+    ///
+    /// let registry = UnitRegistry::new();
+    /// assert_eq!(registry.resolve("Grams"), Unit::Gram);
+    /// assert_eq!(registry.resolve("handful"), Unit::Custom("handful".to_string()));
+    /// ```
+    pub fn resolve(&self, unit: &str) -> Unit {
+        let unit = unit.trim();
+        match unit {
+            "T" => return Unit::Tablespoon,
+            "t" => return Unit::Teaspoon,
+            _ => {}
+        }
+        let lowercase = unit.to_lowercase();
+        let lowercase = lowercase.trim_end_matches('.');
+        let singular = [lowercase.strip_suffix("es"), lowercase.strip_suffix('s')];
+        std::iter::once(Some(lowercase))
+            .chain(singular)
+            .flatten()
+            .find_map(|candidate| self.aliases.get(candidate))
+            .cloned()
+            .unwrap_or_else(|| Unit::Custom(unit.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("g", Unit::Gram)]
+    #[case("gr", Unit::Gram)]
+    #[case("gram", Unit::Gram)]
+    #[case("Grams", Unit::Gram)]
+    #[case(" kg ", Unit::Kilogram)]
+    #[case("kilos", Unit::Kilogram)]
+    #[case("ml", Unit::Milliliter)]
+    #[case("millilitres", Unit::Milliliter)]
+    #[case("L", Unit::Liter)]
+    #[case("cups", Unit::Cup)]
+    #[case("tsp", Unit::Teaspoon)]
+    #[case("t", Unit::Teaspoon)]
+    #[case("T", Unit::Tablespoon)]
+    #[case("Tbsp.", Unit::Tablespoon)]
+    #[case("tablespoons", Unit::Tablespoon)]
+    #[case("fl oz", Unit::FluidOunce)]
+    #[case("lbs", Unit::Pound)]
+    #[case("pinches", Unit::Pinch)]
+    #[case("handfuls", Unit::Custom("handfuls".to_string()))]
+    #[case("Stick", Unit::Custom("Stick".to_string()))]
+    fn test_unit_registry_resolve(#[case] unit: &str, #[case] expected: Unit) {
+        let registry = UnitRegistry::new();
+        assert_eq!(registry.resolve(unit), expected);
+    }
+
+    #[test]
+    fn test_unit_registry_with_alias() {
+        let registry = UnitRegistry::new()
+            .with_alias("Stick", Unit::Custom("stick".to_string()))
+            .with_alias("c", Unit::Custom("clove".to_string()));
+        assert_eq!(
+            registry.resolve("sticks"),
+            Unit::Custom("stick".to_string())
+        );
+        assert_eq!(registry.resolve("c"), Unit::Custom("clove".to_string()));
+    }
+
+    #[rstest]
+    #[case(Unit::Milligram, AmountKind::Weight)]
+    #[case(Unit::Gram, AmountKind::Weight)]
+    #[case(Unit::Kilogram, AmountKind::Weight)]
+    #[case(Unit::Ounce, AmountKind::Weight)]
+    #[case(Unit::Pound, AmountKind::Weight)]
+    #[case(Unit::Milliliter, AmountKind::Volume)]
+    #[case(Unit::Centiliter, AmountKind::Volume)]
+    #[case(Unit::Deciliter, AmountKind::Volume)]
+    #[case(Unit::Liter, AmountKind::Volume)]
+    #[case(Unit::Teaspoon, AmountKind::Volume)]
+    #[case(Unit::Tablespoon, AmountKind::Volume)]
+    #[case(Unit::Cup, AmountKind::Volume)]
+    #[case(Unit::FluidOunce, AmountKind::Volume)]
+    #[case(Unit::Pinch, AmountKind::Volume)]
+    #[case(Unit::Custom("cloves".to_string()), AmountKind::Count)]
+    fn test_unit_kind(#[case] unit: Unit, #[case] expected: AmountKind) {
+        assert_eq!(unit.kind(), expected);
+    }
+
+    #[rstest]
+    #[case(Unit::Gram, "g")]
+    #[case(Unit::Tablespoon, "tbsp")]
+    #[case(Unit::Custom("handful".to_string()), "handful")]
+    fn test_unit_display(#[case] unit: Unit, #[case] expected: &str) {
+        assert_eq!(unit.to_string(), expected);
+    }
+}
@@ -1,13 +1,12 @@
 use console::style;
-use recipe_parser::Token;
+use recipe_parser::{Recipe, Token, display_amounts};
 use std::{fs, path::PathBuf};
 
 use clap::{Parser, Subcommand};
 use inflector::Inflector;
+use std::fmt::Write as _;
 use std::io::Write;
 use tabwriter::TabWriter;
-mod recipe;
-use recipe::Recipe;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
@@ -32,44 +31,43 @@ fn main() {
                     fs::read_to_string(recipe_path).expect("Could not read the given file");
                 let recipe = Recipe::try_from(content.as_str());
                 if let Err(error) = recipe {
-                    eprintln!("Failed to parse the recipe file:\n\n{}", error);
+                    eprintln!(
+                        "Failed to parse the recipe file:\n\n{}",
+                        error.render(&content)
+                    );
                     std::process::exit(1);
                 }
                 let recipe = recipe.unwrap();
                 if let Some(name) = recipe.name {
                     println!("{}\n", style(name.to_title_case()).bold().blue());
                 }
-                if recipe.ingredients.len() > 0 || recipe.recipes_refs.len() > 0 {
+                if !recipe.ingredients.is_empty() || !recipe.recipes_refs.is_empty() {
                     println!("{}\n", style("Ingredients").underlined());
                 }
-                if recipe.ingredients.len() > 0 {
+                if !recipe.ingredients.is_empty() {
                     for ing in recipe.ingredients {
-                        let amount = format!(
-                            "{} {}",
-                            ing.quantity.unwrap_or_default(),
-                            ing.unit.unwrap_or_default()
-                        );
                         let mut tw = TabWriter::new(vec![]).minwidth(32).padding(10);
-                        write!(&mut tw, "  {}\t{}", style(ing.name).cyan().bold(), amount).unwrap();
+                        write!(
+                            &mut tw,
+                            "  {}\t{}",
+                            style(ing.name).cyan().bold(),
+                            display_amounts(&ing.amounts)
+                        )
+                        .unwrap();
                         tw.flush().unwrap();
                         let written = String::from_utf8(tw.into_inner().unwrap()).unwrap();
                         println!("{}", written);
                         // println!("{} {} ", style(ing.name).bold(), amount.trim())
                     }
                 }
-                if recipe.recipes_refs.len() > 0 {
+                if !recipe.recipes_refs.is_empty() {
                     for ing in recipe.recipes_refs {
-                        let amount = format!(
-                            "{} {}",
-                            ing.quantity.unwrap_or_default(),
-                            ing.unit.unwrap_or_default()
-                        );
                         let mut tw = TabWriter::new(vec![]).minwidth(32).padding(10);
                         write!(
                             &mut tw,
                             "  {}\t{}",
                             style(ing.name).magenta().bold(),
-                            amount
+                            display_amounts(&ing.amounts)
                         )
                         .unwrap();
                         tw.flush().unwrap();
@@ -79,29 +77,36 @@ fn main() {
                     }
                 }
                 println!("\n\n{}\n", style("Instructions").underlined().bold());
-                let instru: String = recipe
-                    .instructions
-                    .iter()
-                    .filter_map(|instruct| match instruct {
-                        Token::Metadata { key: _, value: _ } => None,
-                        Token::Ingredient {
-                            name,
-                            quantity: _,
-                            unit: _,
-                        } => Some(format!("{}", style(name).cyan().bold())),
-                        Token::RecipeRef {
-                            name,
-                            quantity: _,
-                            unit: _,
-                        } => Some(format!("{}", style(name).magenta())),
-                        Token::Timer(v) => Some(format!("{}", style(v).red().bold())),
-                        Token::Material(v) => Some(format!("{}", style(v).yellow())),
-                        Token::Word(v) | Token::Space(v) => Some(format!("{v}")),
+                // Written into a single buffer, large recipes have many tokens
+                let mut instru = String::new();
+                for instruct in &recipe.instructions {
+                    match instruct {
+                        Token::Metadata { key: _, value: _ } => Ok(()),
+                        Token::Ingredient { name, amounts: _ } => {
+                            write!(instru, "{}", style(name).cyan().bold())
+                        }
+                        Token::RecipeRef { name, amounts: _ } => {
+                            write!(instru, "{}", style(name).magenta())
+                        }
+                        Token::Timer { duration, .. } => {
+                            write!(instru, "{}", style(duration).red().bold())
+                        }
+                        Token::Material(v) => write!(instru, "{}", style(v).yellow()),
+                        Token::Word(v) | Token::Space(v) => {
+                            instru.push_str(v);
+                            Ok(())
+                        }
+                        Token::Section(v) => write!(instru, "{}", style(v).underlined()),
+                        Token::Link { text, url } => {
+                            write!(instru, "{} ({})", style(text).underlined(), url)
+                        }
+                        Token::Image { alt, url } => write!(instru, "[{}] ({})", alt, url),
 
-                        Token::Comment(_) => None,
-                        Token::Backstory(_) => None,
-                    })
-                    .collect();
+                        Token::Comment(_) => Ok(()),
+                        Token::Backstory(_) => Ok(()),
+                    }
+                    .expect("writing to a String cannot fail");
+                }
 
                 println!("{}", instru.trim())
                 // println!("{}", recipe.instructions);